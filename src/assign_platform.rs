@@ -0,0 +1,135 @@
+use {
+  crate::{
+    show_station::ShowStation,
+    show_stop::ShowStop,
+    show_timetable::ShowTimetable,
+    show_trip::ShowTrip,
+    stop,
+    trip::{self, Trip},
+    Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub trip_id: trip::Id,
+  /// `None` goes back to the route's own first stop.
+  pub platform_id: Option<stop::Id>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  TripNotFound,
+  PlatformNotFound,
+  NotAtOrigin,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TripNotFound => write!(f, "trip not found"),
+      Self::PlatformNotFound => write!(f, "platform not found"),
+      Self::NotAtOrigin => write!(f, "platform is not part of the origin station"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait AssignPlatform {
+  /// Moves the trip's departure to another platform of its origin station.
+  /// The bus assignment needs no new check: a platform of the same station
+  /// leaves times unchanged, and buses are matched up by station.
+  fn assign_platform(&mut self, form: Form) -> Result<(), Error>;
+}
+
+impl AssignPlatform for Database {
+  fn assign_platform(&mut self, form: Form) -> Result<(), Error> {
+    let trip: &Trip = self.show_trip(form.trip_id).ok_or(Error::TripNotFound)?;
+    if let Some(platform_id) = form.platform_id {
+      let platform = self.show_stop(platform_id).ok_or(Error::PlatformNotFound)?;
+      let origin = self
+        .timetable(&Trip {
+          departure_platform: None,
+          ..*trip
+        })
+        .first()
+        .map(|e| self.station_of(e.stop_id));
+      if origin != Some(self.station_of(platform.id)) {
+        return Err(Error::NotAtOrigin);
+      }
+    }
+
+    if let Some(trip) = self.trips.iter_mut().find(|t| t.id == form.trip_id) {
+      trip.departure_platform = form.platform_id;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{fixtures::stops, route},
+  };
+
+  fn database() -> Database {
+    Database {
+      stops: stops(),
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: vec![
+        route::Stop {
+          route_id: route::Id(0),
+          stop_id: stop::Id(1),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          stop_id: stop::Id(3),
+          index: 1,
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn changes_departure_platform() {
+    let mut database = database();
+    let form = Form {
+      trip_id: trip::Id(0),
+      platform_id: Some(stop::Id(2)),
+    };
+
+    assert_eq!(database.assign_platform(form), Ok(()));
+    assert_eq!(
+      database.show_timetable(trip::Id(0)).map(|t| t[0].stop_id),
+      Some(stop::Id(2))
+    );
+  }
+
+  #[test]
+  fn platform_must_be_at_origin() {
+    let mut database = database();
+    let elsewhere = Form {
+      trip_id: trip::Id(0),
+      platform_id: Some(stop::Id(3)),
+    };
+    let missing = Form {
+      trip_id: trip::Id(0),
+      platform_id: Some(stop::Id(9)),
+    };
+
+    assert_eq!(database.assign_platform(elsewhere), Err(Error::NotAtOrigin));
+    assert_eq!(
+      database.assign_platform(missing),
+      Err(Error::PlatformNotFound)
+    );
+  }
+}
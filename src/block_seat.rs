@@ -0,0 +1,143 @@
+use crate::{
+  bool_matrix::BoolMatrix,
+  reserve_seat::{Error, ReserveSeat},
+  seat_block::{self, Reason, SeatBlock},
+  trip, Database,
+};
+
+pub struct Form {
+  pub trip_id: trip::Id,
+  pub seat_index: usize,
+  pub from_stop_index: usize,
+  pub to_stop_index: usize,
+  pub reason: Reason,
+}
+
+#[allow(dead_code)]
+pub trait BlockSeat {
+  fn block_seat(&mut self, form: Form) -> Result<seat_block::Id, Error>;
+}
+
+impl BlockSeat for Database {
+  fn block_seat(&mut self, form: Form) -> Result<seat_block::Id, Error> {
+    let aggr = self.build_aggregate(form.trip_id)?;
+
+    if form.from_stop_index >= aggr.stops_count() {
+      return Err(Error::InvalidFromStop);
+    }
+
+    if form.to_stop_index >= aggr.stops_count() || form.to_stop_index <= form.from_stop_index {
+      return Err(Error::InvalidToStop);
+    }
+
+    if form.seat_index >= aggr.seats_count() {
+      return Err(Error::InvalidSeat);
+    }
+
+    let block = SeatBlock {
+      id: self.next_seat_block_id,
+      trip_id: form.trip_id,
+      seat: form.seat_index,
+      from: form.from_stop_index,
+      to: form.to_stop_index,
+      reason: form.reason,
+    };
+    let mut requested = BoolMatrix::new(aggr.stops_count(), aggr.seats_count());
+    block.apply(&mut requested);
+    let reservations = self.build_occupancy(form.trip_id, &aggr)?;
+
+    if reservations.matrix.has_colliding_bits(&requested) {
+      return Err(Error::SeatAlreadyReserved);
+    }
+    if self
+      .build_blocks(form.trip_id, &aggr)
+      .has_colliding_bits(&requested)
+    {
+      return Err(Error::SeatBlocked);
+    }
+
+    let id = block.id;
+    self.next_seat_block_id = seat_block::Id(id.0.checked_add(1).ok_or(Error::BlockIdsExhausted)?);
+    self.seat_blocks.push(block);
+    Ok(id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      fixtures::{class_database, database},
+      lift_seat_block::LiftSeatBlock,
+      reserve_seat,
+    },
+  };
+
+  #[test]
+  fn blocks_seat_for_reservations() {
+    let mut database = database();
+    let form = Form {
+      trip_id: trip::Id(0),
+      seat_index: 0,
+      from_stop_index: 0,
+      to_stop_index: 1,
+      reason: Reason::CrewRest,
+    };
+
+    assert_eq!(database.block_seat(form), Ok(seat_block::Id(0)));
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        to_stop_index: 1,
+        ..Default::default()
+      }),
+      Err(Error::SeatBlocked)
+    );
+  }
+
+  #[test]
+  fn cannot_block_reserved_seat() {
+    let mut database = database();
+    let _ = database.reserve_seat(reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    });
+    let form = Form {
+      trip_id: trip::Id(0),
+      seat_index: 0,
+      from_stop_index: 0,
+      to_stop_index: 1,
+      reason: Reason::OutOfService,
+    };
+
+    assert_eq!(database.block_seat(form), Err(Error::SeatAlreadyReserved));
+    assert!(database.seat_blocks.is_empty());
+  }
+
+  #[test]
+  fn ids_are_not_reused_and_blocks_do_not_overlap() {
+    let mut database = class_database();
+    let form = |from_stop_index: usize, to_stop_index: usize| Form {
+      trip_id: trip::Id(0),
+      seat_index: 0,
+      from_stop_index,
+      to_stop_index,
+      reason: Reason::CrewRest,
+    };
+
+    assert_eq!(database.block_seat(form(0, 1)), Ok(seat_block::Id(0)));
+    assert_eq!(database.block_seat(form(0, 2)), Err(Error::SeatBlocked));
+    assert_eq!(database.block_seat(form(1, 2)), Ok(seat_block::Id(1)));
+    let _ = database.lift_seat_block(seat_block::Id(1));
+    assert_eq!(database.block_seat(form(1, 2)), Ok(seat_block::Id(2)));
+    database.next_seat_block_id = seat_block::Id(u64::MAX);
+    assert_eq!(
+      database.block_seat(Form {
+        seat_index: 1,
+        ..form(0, 1)
+      }),
+      Err(Error::BlockIdsExhausted)
+    );
+    assert_eq!(database.seat_blocks.len(), 2);
+  }
+}
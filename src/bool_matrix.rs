@@ -1,3 +1,25 @@
+use {
+  crate::varint,
+  std::{fmt, str::FromStr},
+};
+
+/// Largest width or height `decode` accepts, since seats and stop indices are
+/// `u16`.
+pub const MAX_SIDE: usize = u16::MAX as usize;
+
+/// Largest number of cells `decode` accepts, so that a few bytes of input
+/// cannot make it allocate more than 2 MiB.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// Whether `decode` accepts a matrix of these dimensions.
+pub fn fits(width: usize, height: usize) -> bool {
+  width <= MAX_SIDE
+    && height <= MAX_SIDE
+    && width
+      .checked_mul(height)
+      .is_some_and(|cells| cells <= MAX_CELLS)
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct BoolMatrix {
   pub width: usize,
@@ -7,7 +29,7 @@ pub struct BoolMatrix {
 
 impl BoolMatrix {
   pub fn new(width: usize, height: usize) -> Self {
-    let size: usize = (width * height + 7) / 8;
+    let size: usize = (width * height).div_ceil(8);
 
     Self {
       width,
@@ -16,6 +38,7 @@ impl BoolMatrix {
     }
   }
 
+  #[allow(dead_code)]
   pub const fn data(&self) -> &Vec<u8> {
     &self.data
   }
@@ -39,6 +62,7 @@ impl BoolMatrix {
     (self.data[byte_index] & bit_mask) != 0
   }
 
+  #[allow(dead_code)]
   pub fn column(&self, col_index: usize) -> Self {
     let mut column = Self::new(1, self.height);
     for y in 0..self.height {
@@ -48,6 +72,7 @@ impl BoolMatrix {
     column
   }
 
+  #[allow(dead_code)]
   pub fn row(&self, row_index: usize) -> Self {
     let mut row = Self::new(self.width, 1);
     for x in 0..self.width {
@@ -72,7 +97,7 @@ impl BoolMatrix {
     }
   }
 
-  pub fn try_add(one: &BoolMatrix, two: &BoolMatrix) -> Option<Self> {
+  pub fn try_add(one: &Self, two: &Self) -> Option<Self> {
     if one.width != two.width || one.height != two.height {
       return None;
     }
@@ -99,6 +124,223 @@ impl BoolMatrix {
       .zip(other.data.iter())
       .any(|(&byte_self, &byte_other)| byte_self & byte_other != 0)
   }
+
+  /// Compact binary form: a tag byte, the dimensions as varints and then the
+  /// payload in whichever `Encoding` turns out smaller.
+  #[allow(dead_code)]
+  pub fn encode(&self) -> Vec<u8> {
+    let packed = self.encode_with(Encoding::Packed);
+    let run_length = self.encode_with(Encoding::RunLength);
+    if run_length.len() < packed.len() {
+      run_length
+    } else {
+      packed
+    }
+  }
+
+  pub fn encode_with(&self, encoding: Encoding) -> Vec<u8> {
+    let mut buffer: Vec<u8> = vec![encoding as u8];
+    varint::write_usize(&mut buffer, self.width);
+    varint::write_usize(&mut buffer, self.height);
+    match encoding {
+      Encoding::Packed => buffer.extend_from_slice(&self.data),
+      Encoding::RunLength => {
+        for y in 0..self.height {
+          self.encode_row_runs(y, &mut buffer);
+        }
+      }
+    }
+    buffer
+  }
+
+  /// Each row is written as alternating run lengths, starting with a run of
+  /// unset bits (possibly empty), until the runs cover the row width.
+  fn encode_row_runs(&self, y: usize, buffer: &mut Vec<u8>) {
+    let mut current = false;
+    let mut run: usize = 0;
+    for x in 0..self.width {
+      if self.get(x, y) == current {
+        run += 1;
+      } else {
+        varint::write_usize(buffer, run);
+        current = !current;
+        run = 1;
+      }
+    }
+    if run > 0 {
+      varint::write_usize(buffer, run);
+    }
+  }
+
+  #[allow(dead_code)]
+  pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+    let mut cursor: usize = 1;
+    let encoding = match bytes.first() {
+      None => return Err(DecodeError::Truncated),
+      Some(0) => Encoding::Packed,
+      Some(1) => Encoding::RunLength,
+      Some(&tag) => return Err(DecodeError::UnknownEncoding(tag)),
+    };
+    let width = varint::read_usize(bytes, &mut cursor).ok_or(DecodeError::Truncated)?;
+    let height = varint::read_usize(bytes, &mut cursor).ok_or(DecodeError::Truncated)?;
+    if !fits(width, height) {
+      return Err(DecodeError::Oversized);
+    }
+    // Size the buffer from the input before allocating it.
+    match encoding {
+      Encoding::Packed => {
+        let payload = &bytes[cursor..];
+        if payload.len() != (width * height).div_ceil(8) {
+          return Err(DecodeError::InvalidPayload);
+        }
+        // Padding bits past the last cell must be unset, or equal matrices
+        // could decode from different bytes and compare unequal.
+        let used = (width * height) % 8;
+        if used != 0 && payload.last().is_some_and(|&last| last >> used != 0) {
+          return Err(DecodeError::InvalidPayload);
+        }
+        let mut matrix = Self::new(width, height);
+        matrix.data.copy_from_slice(payload);
+        Ok(matrix)
+      }
+      Encoding::RunLength => {
+        let ranges = read_runs(bytes, &mut cursor, width, height)?;
+        if cursor != bytes.len() {
+          return Err(DecodeError::InvalidPayload);
+        }
+        let mut matrix = Self::new(width, height);
+        for (y, from, to) in ranges {
+          matrix.set_row_range(y, from, to, true);
+        }
+        Ok(matrix)
+      }
+    }
+  }
+
+  /// Debugging view with seat labels down the side and stop labels across the
+  /// top. Missing labels fall back to the row or column index.
+  #[allow(dead_code)]
+  pub fn render(&self, row_labels: &[&str], column_labels: &[&str]) -> String {
+    let row_label = |y: usize| {
+      row_labels
+        .get(y)
+        .map_or_else(|| y.to_string(), ToString::to_string)
+    };
+    let column_label = |x: usize| {
+      column_labels
+        .get(x)
+        .map_or_else(|| x.to_string(), ToString::to_string)
+    };
+    let margin = (0..self.height)
+      .map(|y| row_label(y).len())
+      .max()
+      .unwrap_or(0);
+
+    let header: Vec<String> = (0..self.width).map(column_label).collect();
+    let mut lines: Vec<String> = vec![format!("{:margin$} {}", "", header.join(" "))];
+    for y in 0..self.height {
+      let cells: Vec<String> = (0..self.width)
+        .map(|x| {
+          let cell = if self.get(x, y) { '#' } else { '.' };
+          format!("{cell:<width$}", width = header[x].len())
+        })
+        .collect();
+      lines.push(
+        format!("{:margin$} {}", row_label(y), cells.join(" "))
+          .trim_end()
+          .to_string(),
+      );
+    }
+    lines.join("\n")
+  }
+}
+
+/// Set `(row, from, to)` ranges of run-length rows, which must each add up
+/// to exactly `width`. Only the leading run of unset bits of a row may be
+/// empty.
+fn read_runs(
+  bytes: &[u8],
+  cursor: &mut usize,
+  width: usize,
+  height: usize,
+) -> Result<Vec<(usize, usize, usize)>, DecodeError> {
+  let mut ranges = vec![];
+  for y in 0..height {
+    let mut x: usize = 0;
+    let mut value = false;
+    let mut leading = true;
+    while x < width {
+      let run = varint::read_usize(bytes, cursor).ok_or(DecodeError::Truncated)?;
+      let end = x.checked_add(run).ok_or(DecodeError::InvalidPayload)?;
+      if end > width || (run == 0 && !leading) {
+        return Err(DecodeError::InvalidPayload);
+      }
+      if value {
+        ranges.push((y, x, end - 1));
+      }
+      x = end;
+      value = !value;
+      leading = false;
+    }
+  }
+  Ok(ranges)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+  Packed = 0,
+  RunLength = 1,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+  Truncated,
+  UnknownEncoding(u8),
+  Oversized,
+  InvalidPayload,
+  InvalidCell(char),
+  RaggedRows,
+}
+
+/// Plain `#`/`.` grid, one line per row.
+impl fmt::Display for BoolMatrix {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for y in 0..self.height {
+      if y > 0 {
+        writeln!(f)?;
+      }
+      for x in 0..self.width {
+        write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for BoolMatrix {
+  type Err = DecodeError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let rows: Vec<&str> = s.lines().filter(|line| !line.trim().is_empty()).collect();
+    let width = rows.first().map_or(0, |row| row.trim().chars().count());
+    let mut matrix = Self::new(width, rows.len());
+
+    for (y, row) in rows.iter().enumerate() {
+      let row = row.trim();
+      if row.chars().count() != width {
+        return Err(DecodeError::RaggedRows);
+      }
+      for (x, cell) in row.chars().enumerate() {
+        match cell {
+          '#' => matrix.set(x, y, true),
+          '.' => {}
+          other => return Err(DecodeError::InvalidCell(other)),
+        }
+      }
+    }
+
+    Ok(matrix)
+  }
 }
 
 #[cfg(test)]
@@ -113,4 +355,162 @@ mod tests {
     bm.set(0, 0, true);
     assert!(bm.get(0, 0));
   }
+
+  #[test]
+  fn large_sizing() {
+    let bm: BoolMatrix = BoolMatrix::new(320, 300);
+
+    assert_eq!(bm.data().len(), 12_000);
+    assert_eq!(
+      BoolMatrix::new(301, 257).data().len(),
+      (301 * 257usize).div_ceil(8)
+    );
+  }
+
+  #[test]
+  fn large_collisions() {
+    let mut one: BoolMatrix = BoolMatrix::new(320, 300);
+    let mut two: BoolMatrix = BoolMatrix::new(320, 300);
+    one.set_row_range(299, 0, 159, true);
+    two.set_row_range(299, 160, 319, true);
+    two.set_row_range(256, 0, 319, true);
+
+    assert!(!one.has_colliding_bits(&two));
+    let merged = BoolMatrix::try_add(&one, &two).unwrap_or_default();
+    assert!(merged.get(319, 299) && merged.get(0, 299) && merged.get(5, 256));
+    assert!(!merged.get(5, 255));
+
+    let mut three: BoolMatrix = BoolMatrix::new(320, 300);
+    three.set(159, 299, true);
+    assert!(merged.has_colliding_bits(&three));
+    assert_eq!(BoolMatrix::try_add(&merged, &three), None);
+  }
+
+  fn sample() -> BoolMatrix {
+    let mut bm: BoolMatrix = BoolMatrix::new(5, 3);
+    bm.set_row_range(0, 0, 2, true);
+    bm.set_row_range(2, 3, 4, true);
+    bm
+  }
+
+  #[test]
+  fn encode_round_trip() {
+    let bm = sample();
+
+    for encoding in [Encoding::Packed, Encoding::RunLength] {
+      assert_eq!(
+        BoolMatrix::decode(&bm.encode_with(encoding)),
+        Ok(bm.clone())
+      );
+    }
+    assert_eq!(BoolMatrix::decode(&bm.encode()), Ok(bm));
+  }
+
+  #[test]
+  fn encode_prefers_run_length_for_sparse_matrices() {
+    let mut bm: BoolMatrix = BoolMatrix::new(200, 40);
+    bm.set_row_range(7, 10, 150, true);
+
+    let encoded = bm.encode();
+
+    assert_eq!(encoded[0], Encoding::RunLength as u8);
+    assert!(encoded.len() < bm.data().len());
+    assert_eq!(BoolMatrix::decode(&encoded), Ok(bm));
+  }
+
+  #[test]
+  fn decode_rejects_malformed_input() {
+    assert_eq!(BoolMatrix::decode(&[]), Err(DecodeError::Truncated));
+    assert_eq!(
+      BoolMatrix::decode(&[9, 1, 1]),
+      Err(DecodeError::UnknownEncoding(9))
+    );
+    assert_eq!(
+      BoolMatrix::decode(&[0, 8, 2, 0]),
+      Err(DecodeError::InvalidPayload)
+    );
+    assert_eq!(
+      BoolMatrix::decode(&[1, 4, 1, 2, 3]),
+      Err(DecodeError::InvalidPayload)
+    );
+    assert_eq!(
+      BoolMatrix::decode(&[1, 4, 1, 2]),
+      Err(DecodeError::Truncated)
+    );
+    assert_eq!(
+      BoolMatrix::decode(&[1, 4, 1, 0, 0, 4]),
+      Err(DecodeError::InvalidPayload)
+    );
+    let mut full: BoolMatrix = BoolMatrix::new(4, 1);
+    full.set_row_range(0, 0, 3, true);
+    assert_eq!(BoolMatrix::decode(&[1, 4, 1, 0, 4]), Ok(full));
+  }
+
+  #[test]
+  fn decode_checks_dimensions_before_allocating() {
+    let header = |tag: u8, width: u64, height: u64| {
+      let mut bytes = vec![tag];
+      varint::write(&mut bytes, width);
+      varint::write(&mut bytes, height);
+      bytes
+    };
+
+    assert_eq!(
+      BoolMatrix::decode(&header(0, 1 << 40, 1 << 20)),
+      Err(DecodeError::Oversized)
+    );
+    assert_eq!(
+      BoolMatrix::decode(&header(1, 1 << 16, 1 << 16)),
+      Err(DecodeError::Oversized)
+    );
+    assert_eq!(
+      BoolMatrix::decode(&header(0, 0xFFFF, 0xFFFF)),
+      Err(DecodeError::Oversized)
+    );
+    let mut rows = header(1, 0xFFFF, 0xFFFF);
+    rows.extend(vec![0x80; 0xFFFF * 3]);
+    assert_eq!(BoolMatrix::decode(&rows), Err(DecodeError::Oversized));
+
+    let mut rows = header(1, 0x1000, 0x1000);
+    rows.extend([0, 0, 0]);
+    assert_eq!(BoolMatrix::decode(&rows), Err(DecodeError::InvalidPayload));
+    rows.truncate(rows.len() - 2);
+    assert_eq!(BoolMatrix::decode(&rows), Err(DecodeError::Truncated));
+  }
+
+  #[test]
+  fn decode_rejects_set_padding_bits() {
+    let mut bm: BoolMatrix = BoolMatrix::new(5, 1);
+    bm.set(4, 0, true);
+
+    assert_eq!(BoolMatrix::decode(&[0, 5, 1, 0b1_0000]), Ok(bm));
+    assert_eq!(
+      BoolMatrix::decode(&[0, 5, 1, 0b11_0000]),
+      Err(DecodeError::InvalidPayload)
+    );
+  }
+
+  #[test]
+  fn text_round_trip() {
+    let bm = sample();
+    let text = bm.to_string();
+
+    assert_eq!(text, "###..\n.....\n...##");
+    assert_eq!(text.parse::<BoolMatrix>(), Ok(bm));
+    assert_eq!(
+      "#x".parse::<BoolMatrix>(),
+      Err(DecodeError::InvalidCell('x'))
+    );
+    assert_eq!("#.\n#".parse::<BoolMatrix>(), Err(DecodeError::RaggedRows));
+  }
+
+  #[test]
+  fn render_with_labels() {
+    let bm = sample();
+
+    assert_eq!(
+      bm.render(&["1A", "1B", "2A"], &["STG", "RAN"]),
+      "   STG RAN 2 3 4\n1A #   #   # . .\n1B .   .   . . .\n2A .   .   . # #"
+    );
+  }
 }
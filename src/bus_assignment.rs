@@ -0,0 +1,276 @@
+use {
+  crate::{
+    bus,
+    show_station::ShowStation,
+    show_timetable::ShowTimetable,
+    stop,
+    time::Timestamp,
+    trip::{self, Trip},
+    Database,
+  },
+  std::time::Duration,
+};
+
+/// The time and place a bus is busy with one trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Leg {
+  pub trip_id: trip::Id,
+  pub bus_id: bus::Id,
+  pub start: Timestamp,
+  pub end: Timestamp,
+  /// Stations rather than platforms, so a bus may leave from another bay of
+  /// the terminal it arrived at.
+  pub origin: Option<stop::Id>,
+  pub destination: Option<stop::Id>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictKind {
+  /// The later trip starts before the earlier one ends plus the turnaround.
+  Overlap,
+  /// The later trip does not start where the earlier one ended.
+  Location,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+  pub bus_id: bus::Id,
+  pub earlier: trip::Id,
+  pub later: trip::Id,
+  pub kind: ConflictKind,
+}
+
+fn leg(database: &Database, trip: &Trip, bus_id: bus::Id) -> Leg {
+  let timetable = database.timetable(trip);
+  Leg {
+    trip_id: trip.id,
+    bus_id,
+    start: timetable.first().map_or(trip.departure, |e| e.departure),
+    end: timetable.last().map_or(trip.departure, |e| e.arrival),
+    origin: timetable.first().map(|e| database.station_of(e.stop_id)),
+    destination: timetable.last().map(|e| database.station_of(e.stop_id)),
+  }
+}
+
+/// Conflicts among legs of a single bus, which must be sorted by start.
+fn conflicts(legs: &[Leg], turnaround: Duration) -> Vec<Conflict> {
+  let mut found: Vec<Conflict> = vec![];
+  for (i, earlier) in legs.iter().enumerate() {
+    for (j, later) in legs.iter().enumerate().skip(i + 1) {
+      let kind = if later.start < earlier.end.add(turnaround) {
+        ConflictKind::Overlap
+      } else if j == i + 1
+        && earlier.destination.is_some()
+        && later.origin.is_some()
+        && earlier.destination != later.origin
+      {
+        ConflictKind::Location
+      } else {
+        continue;
+      };
+      found.push(Conflict {
+        bus_id: earlier.bus_id,
+        earlier: earlier.trip_id,
+        later: later.trip_id,
+        kind,
+      });
+    }
+  }
+  found
+}
+
+pub trait CheckBusAssignment {
+  /// Checks `trip` against the trips already assigned to its bus. A trip
+  /// with only a bus model has none to conflict with.
+  fn check_bus_assignment(&self, trip: &Trip, turnaround: Duration) -> Result<(), Conflict>;
+
+  /// Every conflict between trips already in the database.
+  #[allow(dead_code)]
+  fn list_bus_conflicts(&self, turnaround: Duration) -> Vec<Conflict>;
+}
+
+impl CheckBusAssignment for Database {
+  fn check_bus_assignment(&self, trip: &Trip, turnaround: Duration) -> Result<(), Conflict> {
+    let Some(bus_id) = trip.vehicle.bus_id() else {
+      return Ok(());
+    };
+    let mut legs: Vec<Leg> = self
+      .trips
+      .iter()
+      .filter(|t| t.vehicle.bus_id() == Some(bus_id) && t.id != trip.id)
+      .map(|t| leg(self, t, bus_id))
+      .collect();
+    legs.push(leg(self, trip, bus_id));
+    legs.sort_by_key(|l| (l.start, l.trip_id.0));
+
+    conflicts(&legs, turnaround)
+      .into_iter()
+      .find(|c| c.earlier == trip.id || c.later == trip.id)
+      .map_or(Ok(()), Err)
+  }
+
+  fn list_bus_conflicts(&self, turnaround: Duration) -> Vec<Conflict> {
+    let mut legs: Vec<Leg> = self
+      .trips
+      .iter()
+      .filter_map(|t| Some(leg(self, t, t.vehicle.bus_id()?)))
+      .collect();
+    legs.sort_by_key(|l| (l.bus_id.0, l.start, l.trip_id.0));
+
+    legs
+      .chunk_by(|a, b| a.bus_id == b.bus_id)
+      .flat_map(|bus_legs| conflicts(bus_legs, turnaround))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{bus_model, fixtures::stops, route, trip::Vehicle},
+  };
+
+  fn database() -> Database {
+    let route_stops = |route: u64, from: u64, to: u64| {
+      [
+        route::Stop {
+          route_id: route::Id(route),
+          stop_id: stop::Id(from),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(route),
+          stop_id: stop::Id(to),
+          index: 1,
+          arrival_offset: Duration::from_hours(1),
+          departure_offset: Duration::from_hours(1),
+          ..Default::default()
+        },
+      ]
+    };
+    Database {
+      route_stops: [route_stops(0, 0, 1), route_stops(1, 1, 0)].concat(),
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        departure: Timestamp(0),
+        ..Default::default()
+      }],
+      ..Default::default()
+    }
+  }
+
+  fn trip(id: u64, route: u64, departure: i64) -> Trip {
+    Trip {
+      id: trip::Id(id),
+      route_id: route::Id(route),
+      departure: Timestamp(departure),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn return_trip_after_turnaround() {
+    let database = database();
+
+    assert_eq!(
+      database.check_bus_assignment(&trip(1, 1, 4_200), Duration::from_mins(10)),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn overlap_within_turnaround() {
+    let database = database();
+
+    assert_eq!(
+      database.check_bus_assignment(&trip(1, 1, 3_900), Duration::from_mins(10)),
+      Err(Conflict {
+        bus_id: bus::Id(0),
+        earlier: trip::Id(0),
+        later: trip::Id(1),
+        kind: ConflictKind::Overlap,
+      })
+    );
+  }
+
+  #[test]
+  fn next_trip_starts_elsewhere() {
+    let database = database();
+
+    assert_eq!(
+      database.check_bus_assignment(&trip(1, 0, 7_200), Duration::ZERO),
+      Err(Conflict {
+        bus_id: bus::Id(0),
+        earlier: trip::Id(0),
+        later: trip::Id(1),
+        kind: ConflictKind::Location,
+      })
+    );
+  }
+
+  #[test]
+  fn next_trip_leaves_from_another_platform() {
+    let mut database = database();
+    database.stops = stops();
+    database.route_stops[1].stop_id = stop::Id(2);
+    database.route_stops[2].stop_id = stop::Id(1);
+
+    assert_eq!(
+      database.check_bus_assignment(&trip(1, 1, 7_200), Duration::ZERO),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn other_buses_are_ignored() {
+    let database = database();
+    let other_bus = Trip {
+      vehicle: Vehicle::Bus(bus::Id(1)),
+      ..trip(1, 0, 0)
+    };
+    let no_bus = Trip {
+      vehicle: Vehicle::BusModel(bus_model::Id(0)),
+      ..trip(1, 0, 0)
+    };
+
+    assert_eq!(
+      database.check_bus_assignment(&other_bus, Duration::ZERO),
+      Ok(())
+    );
+    assert_eq!(
+      database.check_bus_assignment(&no_bus, Duration::ZERO),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn list_conflicts() {
+    let mut database = database();
+    database.trips.push(trip(1, 1, 1_800));
+    database.trips.push(trip(2, 1, 7_200));
+    database.trips.push(Trip {
+      vehicle: Vehicle::Bus(bus::Id(1)),
+      ..trip(3, 0, 0)
+    });
+
+    assert_eq!(
+      database.list_bus_conflicts(Duration::ZERO),
+      vec![
+        Conflict {
+          bus_id: bus::Id(0),
+          earlier: trip::Id(0),
+          later: trip::Id(1),
+          kind: ConflictKind::Overlap,
+        },
+        Conflict {
+          bus_id: bus::Id(0),
+          earlier: trip::Id(1),
+          later: trip::Id(2),
+          kind: ConflictKind::Location,
+        },
+      ]
+    );
+  }
+}
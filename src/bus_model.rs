@@ -1,3 +1,5 @@
+use crate::seat_layout::{Seat, SeatLayout};
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Id(pub u64);
 
@@ -5,5 +7,21 @@ pub struct Id(pub u64);
 pub struct BusModel {
   pub id: Id,
   pub name: String,
-  pub number_of_seats: u8,
+  pub number_of_seats: u16,
+  /// Models without a layout only know seats by index.
+  pub layout: Option<SeatLayout>,
+}
+
+impl BusModel {
+  pub fn seats_count(&self) -> usize {
+    usize::from(self.number_of_seats)
+  }
+
+  pub fn seat(&self, index: usize) -> Option<&Seat> {
+    self.layout.as_ref()?.seat(index)
+  }
+
+  pub fn seat_index(&self, label: &str) -> Option<usize> {
+    self.layout.as_ref()?.seat_index(label)
+  }
 }
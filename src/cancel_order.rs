@@ -0,0 +1,139 @@
+use {
+  crate::{
+    cancel_ticket::{self, CancelTicket},
+    order::{self, Status},
+    payment,
+    payment_provider::PaymentProvider,
+    process_payment::{self, ProcessPayment},
+    refund::Refund,
+    reserve_seat::ReserveSeat,
+    seat_reservation::{self, SeatReservation},
+    time::Timestamp,
+    Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub order_id: order::Id,
+  pub requested_at: Timestamp,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  OrderNotFound,
+  AlreadyCancelled,
+  Ticket(cancel_ticket::Error),
+  Payment(process_payment::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::OrderNotFound => write!(f, "order not found"),
+      Self::AlreadyCancelled => write!(f, "order is already cancelled"),
+      Self::Ticket(error) => write!(f, "{error}"),
+      Self::Payment(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cancellation {
+  /// Every seat the order had, held or ticketed.
+  pub released: Vec<SeatReservation>,
+  /// One per ticket, as its cancellation policy allows.
+  pub refunds: Vec<Refund>,
+}
+
+#[allow(dead_code)]
+pub trait CancelOrder {
+  /// Releases every seat of the order on all of its trips and marks it
+  /// cancelled. A payment that was not captured is voided; tickets are
+  /// cancelled one by one so each is refunded by its route's policy. Every
+  /// ticket's refund is worked out before the provider is called, so a
+  /// departed trip, a fee that cannot be converted or refunds beyond the
+  /// captured amount fail without changes. A void or refund that fails stops
+  /// it with the earlier payments already voided and the earlier tickets
+  /// already cancelled, leaving the order open so it can be cancelled again.
+  fn cancel_order(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Cancellation, Error>;
+}
+
+impl CancelOrder for Database {
+  fn cancel_order(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Cancellation, Error> {
+    let id = form.order_id;
+    let order = self
+      .orders
+      .iter()
+      .find(|o| o.id == id)
+      .ok_or(Error::OrderNotFound)?;
+    if order.status == Status::Cancelled {
+      return Err(Error::AlreadyCancelled);
+    }
+    let tickets: Vec<SeatReservation> = self
+      .seat_reservations
+      .iter()
+      .filter(|sr| sr.order_id() == Some(id) && sr.status() == seat_reservation::Status::Ticketed)
+      .cloned()
+      .collect();
+    self
+      .check_cancellations(&tickets, form.requested_at)
+      .map_err(Error::Ticket)?;
+
+    let open: Vec<payment::Id> = self
+      .payments
+      .iter()
+      .filter(|p| {
+        p.order_id == id
+          && matches!(
+            p.status,
+            payment::Status::Created | payment::Status::Authorized
+          )
+      })
+      .map(|p| p.id)
+      .collect();
+    for payment_id in open {
+      self
+        .void_payment(payment_id, provider)
+        .map_err(Error::Payment)?;
+    }
+    let mut refunds = Vec::with_capacity(tickets.len());
+    for ticket in &tickets {
+      let refund = self
+        .cancel_ticket(
+          cancel_ticket::Form {
+            trip_id: ticket.trip_id(),
+            seat_index: ticket.seat(),
+            stop_index: ticket.first_stop(),
+            requested_at: form.requested_at,
+          },
+          provider,
+        )
+        .map_err(Error::Ticket)?;
+      refunds.push(refund);
+    }
+
+    let (mut released, kept): (Vec<SeatReservation>, Vec<SeatReservation>) = self
+      .seat_reservations
+      .drain(..)
+      .partition(|sr| sr.order_id() == Some(id));
+    self.seat_reservations = kept;
+    for reservation in &released {
+      self.release_promo_code(reservation);
+    }
+    if let Some(order) = self.orders.iter_mut().find(|o| o.id == id) {
+      order.status = Status::Cancelled;
+    }
+    released.extend(tickets);
+    Ok(Cancellation { released, refunds })
+  }
+}
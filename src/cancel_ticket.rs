@@ -0,0 +1,363 @@
+use {
+  crate::{
+    money::Money,
+    order,
+    payment_provider::PaymentProvider,
+    place_order::PlaceOrder,
+    process_payment::{self, ProcessPayment},
+    refund::Refund,
+    reserve_seat::{self, ReserveSeat},
+    seat_reservation::{self, SeatReservation},
+    show_payment::ShowPayment,
+    show_ticket::ShowTicket,
+    time::Timestamp,
+    trip, Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub trip_id: trip::Id,
+  pub seat_index: usize,
+  /// Any stop the passenger leaves on board, from boarding on.
+  pub stop_index: usize,
+  pub requested_at: Timestamp,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  TicketNotFound,
+  /// The seat is only held; release it by cancelling its order.
+  NotTicketed,
+  TripDeparted,
+  /// A fixed cancellation fee cannot be brought into the ticket's currency.
+  CurrencyMismatch,
+  Reservation(reserve_seat::Error),
+  Payment(process_payment::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TicketNotFound => write!(f, "ticket not found"),
+      Self::NotTicketed => write!(f, "seat is held, not ticketed"),
+      Self::TripDeparted => write!(f, "trip has departed"),
+      Self::CurrencyMismatch => write!(f, "cancellation fee is in another currency"),
+      Self::Reservation(error) => write!(f, "{error}"),
+      Self::Payment(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+pub trait CancelTicket {
+  /// Frees the ticket's seat over its stops and pays back what the route's
+  /// cancellation policy allows through the order's captured payments. The
+  /// order's total drops by the ticket, and the order is cancelled with its
+  /// last seat. A refund that fails keeps the ticket, unless part of it was
+  /// already returned: the ticket is then cancelled with that part recorded
+  /// and the error reported.
+  fn cancel_ticket(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Refund, Error>;
+
+  /// `(refund, fee)` the route's cancellation policy gives for the ticket
+  /// at `requested_at`.
+  fn cancellation_refund(
+    &self,
+    ticket: &SeatReservation,
+    requested_at: Timestamp,
+  ) -> Result<(Money, Money), Error>;
+
+  /// Checks, without calling the provider, that every ticket can be
+  /// cancelled at `requested_at` and that each order's captured payments
+  /// cover the refunds of its tickets.
+  fn check_cancellations(
+    &self,
+    tickets: &[SeatReservation],
+    requested_at: Timestamp,
+  ) -> Result<(), Error>;
+}
+
+impl CancelTicket for Database {
+  fn cancel_ticket(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Refund, Error> {
+    let position = self
+      .find_ticket(form.trip_id, form.seat_index, form.stop_index)
+      .ok_or(Error::TicketNotFound)?;
+    let ticket = &self.seat_reservations[position];
+    if ticket.status() != seat_reservation::Status::Ticketed {
+      return Err(Error::NotTicketed);
+    }
+
+    let (due, fee) = self.cancellation_refund(ticket, form.requested_at)?;
+    let (payment_ids, amount, failure) = match ticket.order_id() {
+      Some(order_id) if due.amount > 0 && self.refundable(order_id).is_some() => {
+        match self.refund_order(order_id, due, provider) {
+          Ok(payment_ids) => (payment_ids, due, None),
+          Err(error) => {
+            let process_payment::Error::PartiallyRefunded {
+              payment_ids,
+              refunded,
+              ..
+            } = &error
+            else {
+              return Err(Error::Payment(error));
+            };
+            (payment_ids.clone(), *refunded, Some(error))
+          }
+        }
+      }
+      _ => (vec![], Money::zero(due.currency), None),
+    };
+    let ticket = self.seat_reservations.remove(position);
+    self.release_promo_code(&ticket);
+    if let Some(order_id) = ticket.order_id() {
+      // The remaining seats are a subset of a total that already added up.
+      let total = self.order_total(order_id).ok().flatten();
+      let empty = !self
+        .seat_reservations
+        .iter()
+        .any(|sr| sr.order_id() == Some(order_id));
+      if let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) {
+        order.total = total;
+        if empty {
+          order.status = order::Status::Cancelled;
+        }
+      }
+    }
+    let refund = Refund {
+      trip_id: ticket.trip_id(),
+      seat: ticket.seat(),
+      from: ticket.first_stop(),
+      to: ticket.last_stop(),
+      order_id: ticket.order_id(),
+      passenger_id: ticket.passenger_id(),
+      payment_ids,
+      amount,
+      fee,
+      cancelled_at: form.requested_at,
+    };
+    self.refunds.push(refund.clone());
+    failure.map_or(Ok(refund), |error| Err(Error::Payment(error)))
+  }
+
+  fn cancellation_refund(
+    &self,
+    ticket: &SeatReservation,
+    requested_at: Timestamp,
+  ) -> Result<(Money, Money), Error> {
+    let aggr = self
+      .build_aggregate(ticket.trip_id())
+      .map_err(Error::Reservation)?;
+    let until_departure = aggr
+      .until_departure(ticket.first_stop(), requested_at)
+      .ok_or(Error::TripDeparted)?;
+    let route = aggr.route();
+    ticket
+      .quote()
+      .map_or_else(
+        || Ok((Money::zero(route.currency), Money::zero(route.currency))),
+        |quote| {
+          route.cancellation.refund(
+            quote.total,
+            quote.fare_type_id,
+            until_departure,
+            route.rounding,
+            route.convert_to(quote.total.currency),
+          )
+        },
+      )
+      .map_err(|_| Error::CurrencyMismatch)
+  }
+
+  fn check_cancellations(
+    &self,
+    tickets: &[SeatReservation],
+    requested_at: Timestamp,
+  ) -> Result<(), Error> {
+    let mut refunds: Vec<(order::Id, Money)> = vec![];
+    for ticket in tickets {
+      let (amount, _) = self.cancellation_refund(ticket, requested_at)?;
+      let Some(order_id) = ticket.order_id() else {
+        continue;
+      };
+      match refunds.iter_mut().find(|(id, _)| *id == order_id) {
+        Some((_, total)) => {
+          *total = total.checked_add(amount).ok_or(Error::CurrencyMismatch)?;
+        }
+        None => refunds.push((order_id, amount)),
+      }
+    }
+    for (order_id, amount) in refunds {
+      if let Some(refundable) = self.refundable(order_id) {
+        refundable
+          .checked_sub(amount)
+          .ok_or(Error::Payment(process_payment::Error::RefundTooLarge))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      cancellation::{CancellationPolicy, FeeWindow},
+      discount::Discount,
+      fixtures::{item, ticketed},
+      money::Currency,
+      payment::{self, Failure},
+      payment_provider::FakeProvider,
+    },
+    std::time::Duration,
+  };
+
+  /// The paid test order, refundable less 10 % until a day before
+  /// departure.
+  fn refundable(provider: &mut FakeProvider) -> Database {
+    let mut database = ticketed(provider);
+    database.routes[0].cancellation = CancellationPolicy {
+      windows: vec![FeeWindow {
+        before: Duration::from_hours(24),
+        fee: Discount::Percent(10),
+      }],
+      non_refundable_fare_types: vec![],
+    };
+    database
+  }
+
+  fn form(trip_id: u64, requested_at: i64) -> Form {
+    Form {
+      trip_id: trip::Id(trip_id),
+      seat_index: 0,
+      stop_index: 0,
+      requested_at: Timestamp(requested_at),
+    }
+  }
+
+  #[test]
+  fn refunds_by_window_and_frees_the_seat() {
+    let mut provider = FakeProvider::default();
+    let mut database = refundable(&mut provider);
+
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider).map(|r| (
+        r.amount.amount,
+        r.fee.amount,
+        r.payment_ids
+      )),
+      Ok((900, 100, vec![payment::Id(0)]))
+    );
+    assert_eq!(provider.calls.last().map(|call| call.0), Some("refund"));
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_000, Currency::XXX))
+    );
+    assert_eq!(database.reserve_seat(item(0, 0)), Ok(()));
+    assert_eq!(
+      database
+        .cancel_ticket(form(1, 100_000 - 3_600), &mut provider)
+        .map(|r| (r.amount.amount, r.fee.amount)),
+      Ok((0, 1_000))
+    );
+    assert_eq!(provider.calls.len(), 3);
+    assert_eq!(database.refunds.len(), 2);
+    assert_eq!(database.payments[0].refunded.amount, 900);
+    assert_eq!(database.orders[0].total, None);
+    assert_eq!(database.orders[0].status, order::Status::Cancelled);
+    assert_eq!(
+      database.cancel_ticket(form(1, 0), &mut provider),
+      Err(Error::TicketNotFound)
+    );
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider),
+      Err(Error::NotTicketed)
+    );
+  }
+
+  #[test]
+  fn failed_refund_keeps_the_ticket() {
+    let mut provider = FakeProvider::default();
+    let mut database = refundable(&mut provider);
+    provider.fail_next(Failure::TimedOut);
+
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider),
+      Err(Error::Payment(process_payment::Error::Provider(
+        Failure::TimedOut
+      )))
+    );
+    assert_eq!(database.seat_reservations.len(), 2);
+    assert!(database.refunds.is_empty());
+  }
+
+  #[test]
+  fn partial_refund_cancels_the_ticket() {
+    let mut provider = FakeProvider::default();
+    let mut database = refundable(&mut provider);
+    let _ = database.charge_order(order::Id(0), Money::new(100, Currency::XXX), &mut provider);
+    provider.succeed_next();
+    provider.fail_next(Failure::Declined);
+
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider),
+      Err(Error::Payment(process_payment::Error::PartiallyRefunded {
+        payment_ids: vec![payment::Id(1)],
+        refunded: Money::new(100, Currency::XXX),
+        error: Box::new(process_payment::Error::Provider(Failure::Declined)),
+      }))
+    );
+    assert_eq!(
+      database
+        .refunds
+        .iter()
+        .map(|r| (r.amount.amount, r.payment_ids.clone()))
+        .collect::<Vec<_>>(),
+      [(100, vec![payment::Id(1)])]
+    );
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider),
+      Err(Error::TicketNotFound)
+    );
+  }
+
+  #[test]
+  fn nothing_is_refunded_without_a_payment() {
+    let mut provider = FakeProvider::default();
+    let mut database = refundable(&mut provider);
+    database.seat_reservations[0].set_order_id(None);
+
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider).map(|r| (
+        r.amount.amount,
+        r.fee.amount,
+        r.payment_ids
+      )),
+      Ok((0, 100, vec![]))
+    );
+    assert!(provider.calls.iter().all(|call| call.0 != "refund"));
+  }
+
+  #[test]
+  fn fee_in_another_currency_is_rejected() {
+    let mut provider = FakeProvider::default();
+    let mut database = refundable(&mut provider);
+    database.routes[0].cancellation.windows[0].fee =
+      Discount::Fixed(Money::new(100, Currency(*b"USD")));
+
+    assert_eq!(
+      database.cancel_ticket(form(0, 0), &mut provider),
+      Err(Error::CurrencyMismatch)
+    );
+    assert!(provider.calls.iter().all(|call| call.0 != "refund"));
+    assert_eq!(database.seat_reservations.len(), 2);
+  }
+}
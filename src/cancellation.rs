@@ -0,0 +1,183 @@
+use {
+  crate::{
+    discount::Discount,
+    fare_type,
+    money::{Exact, Money, Rounding},
+  },
+  std::time::Duration,
+};
+
+/// A fixed fee that cannot be brought into the ticket's currency.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CurrencyMismatch;
+
+/// How much of a ticket's price is returned on cancellation. Without
+/// windows, tickets are refunded in full until departure.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CancellationPolicy {
+  /// Their fee is kept off the refund.
+  pub windows: Vec<FeeWindow>,
+  /// Fare types whose tickets are never refunded.
+  pub non_refundable_fare_types: Vec<fare_type::Id>,
+}
+
+/// A fee that applies while at least `before` is left until departure,
+/// unless a window with a longer `before` does too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeWindow {
+  pub before: Duration,
+  pub fee: Discount,
+}
+
+impl CancellationPolicy {
+  /// `(refund, fee)` for a ticket that cost `price`. Cancelling later than
+  /// every window refunds nothing. A fixed fee is brought into `price`'s
+  /// currency by `convert`.
+  pub fn refund(
+    &self,
+    price: Money,
+    fare_type_id: Option<fare_type::Id>,
+    until_departure: Duration,
+    rounding: Rounding,
+    convert: impl Fn(Money) -> Option<Exact>,
+  ) -> Result<(Money, Money), CurrencyMismatch> {
+    let nothing = (Money::zero(price.currency), price);
+    if fare_type_id.is_some_and(|id| self.non_refundable_fare_types.contains(&id)) {
+      return Ok(nothing);
+    }
+    if self.windows.is_empty() {
+      return Ok((price, Money::zero(price.currency)));
+    }
+    let Some(window) = self
+      .windows
+      .iter()
+      .filter(|w| until_departure >= w.before)
+      .max_by_key(|w| w.before)
+    else {
+      return Ok(nothing);
+    };
+    let fee = window.charge(price, rounding, convert)?;
+    let refund = price.checked_sub(fee).ok_or(CurrencyMismatch)?;
+    Ok((refund, fee))
+  }
+}
+
+/// Fee for moving a ticket to another trip or seat. Without windows,
+/// changes are free until departure.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChangePolicy {
+  pub windows: Vec<FeeWindow>,
+  /// Whether moving to a cheaper fare pays back the difference.
+  pub refund_difference: bool,
+}
+
+impl ChangePolicy {
+  /// Fee to change a ticket that cost `price`, or `None` when it is later
+  /// than every window. A fixed fee is brought into `price`'s currency by
+  /// `convert`.
+  pub fn fee(
+    &self,
+    price: Money,
+    until_departure: Duration,
+    rounding: Rounding,
+    convert: impl Fn(Money) -> Option<Exact>,
+  ) -> Result<Option<Money>, CurrencyMismatch> {
+    if self.windows.is_empty() {
+      return Ok(Some(Money::zero(price.currency)));
+    }
+    self
+      .windows
+      .iter()
+      .filter(|w| until_departure >= w.before)
+      .max_by_key(|w| w.before)
+      .map(|window| window.charge(price, rounding, convert))
+      .transpose()
+  }
+}
+
+impl FeeWindow {
+  /// The fee on a ticket that cost `price`, never more than it.
+  fn charge(
+    &self,
+    price: Money,
+    rounding: Rounding,
+    convert: impl Fn(Money) -> Option<Exact>,
+  ) -> Result<Money, CurrencyMismatch> {
+    self
+      .fee
+      .exact_off(price.into(), convert)
+      .and_then(|fee| fee.round(rounding))
+      .ok_or(CurrencyMismatch)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::money::{Currency, ExchangeRate},
+  };
+
+  #[test]
+  fn windows_and_fare_types() {
+    let policy = CancellationPolicy {
+      windows: vec![
+        FeeWindow {
+          before: Duration::from_hours(2),
+          fee: Discount::Percent(50),
+        },
+        FeeWindow {
+          before: Duration::from_hours(48),
+          fee: Discount::Fixed(Money::new(100, Currency::XXX)),
+        },
+      ],
+      non_refundable_fare_types: vec![fare_type::Id(1)],
+    };
+    let refund = |fare_type: Option<u64>, hours: u64| {
+      policy
+        .refund(
+          Money::new(1_000, Currency::XXX),
+          fare_type.map(fare_type::Id),
+          Duration::from_hours(hours),
+          Rounding::HalfUp,
+          |amount| Some(amount.into()),
+        )
+        .map(|(refund, fee)| (refund.amount, fee.amount))
+        .unwrap_or_default()
+    };
+
+    assert_eq!(refund(None, 72), (900, 100));
+    assert_eq!(refund(Some(0), 3), (500, 500));
+    assert_eq!(refund(None, 1), (0, 1_000));
+    assert_eq!(refund(Some(1), 72), (0, 1_000));
+  }
+
+  #[test]
+  fn fixed_fees_are_converted() {
+    let (clp, ars) = (Currency(*b"CLP"), Currency(*b"ARS"));
+    let policy = ChangePolicy {
+      windows: vec![FeeWindow {
+        before: Duration::ZERO,
+        fee: Discount::Fixed(Money::new(1_005, clp)),
+      }],
+      refund_difference: false,
+    };
+    let rate = ExchangeRate::new(ars, 1, 10);
+    let convert = |amount: Money| match rate {
+      Some(rate) if amount.currency == clp => rate.convert(amount.into()),
+      _ => None,
+    };
+    let fee = |price: u64, currency| {
+      policy.fee(
+        Money::new(price, currency),
+        Duration::ZERO,
+        Rounding::HalfEven,
+        convert,
+      )
+    };
+
+    assert_eq!(fee(1_000, ars), Ok(Some(Money::new(100, ars))));
+    assert_eq!(fee(50, ars), Ok(Some(Money::new(50, ars))));
+    assert_eq!(fee(1_000, Currency(*b"USD")), Err(CurrencyMismatch));
+  }
+}
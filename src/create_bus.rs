@@ -1,6 +1,5 @@
 use crate::{bus::Bus, Database};
 
-
 pub trait CreateBus {
   fn create_bus(&mut self, form: Bus);
 }
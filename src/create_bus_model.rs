@@ -1,12 +1,82 @@
-use crate::{bus_model::BusModel, Database};
+use {
+  crate::{bus_model::BusModel, seat_layout, Database},
+  std::{error, fmt::Display},
+};
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  Layout(seat_layout::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a bus model with this id exists"),
+      Self::Layout(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
 
 pub trait CreateBusModel {
-  fn create_bus_model(&mut self, form: BusModel);
+  fn create_bus_model(&mut self, form: BusModel) -> Result<(), Error>;
 }
 
 impl CreateBusModel for Database {
-  fn create_bus_model(&mut self, form: BusModel) {
+  fn create_bus_model(&mut self, form: BusModel) -> Result<(), Error> {
+    if self.bus_models.iter().any(|m| m.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    if let Some(layout) = &form.layout {
+      layout.validate(form.seats_count()).map_err(Error::Layout)?;
+    }
     self.bus_models.push(form);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{bus_model, seat_layout::SeatLayout},
+  };
+
+  #[test]
+  fn without_layout() {
+    let mut database: Database = Database::default();
+    let form = || BusModel {
+      id: bus_model::Id(0),
+      number_of_seats: 40,
+      ..Default::default()
+    };
+
+    assert_eq!(database.create_bus_model(form()), Ok(()));
+    assert_eq!(database.create_bus_model(form()), Err(Error::DuplicateId));
+    assert_eq!(database.bus_models.len(), 1);
+  }
+
+  #[test]
+  fn layout_must_match_seat_count() {
+    let mut database: Database = Database::default();
+    let form = BusModel {
+      id: bus_model::Id(0),
+      number_of_seats: 40,
+      layout: Some(SeatLayout {
+        decks: 1,
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.create_bus_model(form),
+      Err(Error::Layout(seat_layout::Error::SeatCountMismatch {
+        layout: 0,
+        model: 40
+      }))
+    );
+    assert!(database.bus_models.is_empty());
   }
 }
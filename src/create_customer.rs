@@ -0,0 +1,84 @@
+use {
+  crate::{customer::Customer, passenger::Contact, Database},
+  std::{error, fmt::Display},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  EmptyName,
+  InvalidEmail,
+  DuplicateEmail,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a customer with this id exists"),
+      Self::EmptyName => write!(f, "customer name is empty"),
+      Self::InvalidEmail => write!(f, "email address is not valid"),
+      Self::DuplicateEmail => write!(f, "an account with this email exists"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait CreateCustomer {
+  fn create_customer(&mut self, form: Customer) -> Result<(), Error>;
+}
+
+impl CreateCustomer for Database {
+  fn create_customer(&mut self, form: Customer) -> Result<(), Error> {
+    if self.customers.iter().any(|c| c.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    if form.name.trim().is_empty() {
+      return Err(Error::EmptyName);
+    }
+    if !Contact::is_valid_email(&form.email) {
+      return Err(Error::InvalidEmail);
+    }
+    if self
+      .customers
+      .iter()
+      .any(|c| c.email.eq_ignore_ascii_case(&form.email))
+    {
+      return Err(Error::DuplicateEmail);
+    }
+    self.customers.push(form);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::customer};
+
+  #[test]
+  fn unique_email() {
+    let mut database = Database::default();
+    let customer = Customer {
+      id: customer::Id(0),
+      name: "Ana Rojas".to_string(),
+      email: "ana@example.cl".to_string(),
+    };
+
+    assert_eq!(database.create_customer(customer.clone()), Ok(()));
+    assert_eq!(
+      database.create_customer(Customer {
+        id: customer::Id(1),
+        email: "ANA@example.cl".to_string(),
+        ..customer.clone()
+      }),
+      Err(Error::DuplicateEmail)
+    );
+    assert_eq!(
+      database.create_customer(Customer {
+        email: "ana.rojas@example.cl".to_string(),
+        ..customer
+      }),
+      Err(Error::DuplicateId)
+    );
+  }
+}
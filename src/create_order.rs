@@ -0,0 +1,39 @@
+use {
+  crate::{order::Order, Database},
+  std::{error, fmt::Display},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  CustomerNotFound,
+  DuplicateId,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::CustomerNotFound => write!(f, "customer not found"),
+      Self::DuplicateId => write!(f, "an order with this id exists"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+pub trait CreateOrder {
+  /// Opens an empty order; reservations join it through
+  /// `reserve_seat::Form::order_id`.
+  fn create_order(&mut self, form: Order) -> Result<(), Error>;
+}
+
+impl CreateOrder for Database {
+  fn create_order(&mut self, form: Order) -> Result<(), Error> {
+    if !self.customers.iter().any(|c| c.id == form.customer_id) {
+      return Err(Error::CustomerNotFound);
+    }
+    if self.orders.iter().any(|o| o.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    self.orders.push(form);
+    Ok(())
+  }
+}
@@ -0,0 +1,147 @@
+use {
+  crate::{
+    passenger::{Contact, Passenger},
+    show_passenger::ShowPassenger,
+    Database,
+  },
+  std::{error, fmt::Display},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  EmptyName,
+  InvalidDocument,
+  InvalidNationality,
+  InvalidEmail,
+  InvalidPhone,
+  DuplicateDocument,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a passenger with this id exists"),
+      Self::EmptyName => write!(f, "passenger name is empty"),
+      Self::InvalidDocument => write!(f, "document number is not valid for its type"),
+      Self::InvalidNationality => write!(f, "nationality is not a country code"),
+      Self::InvalidEmail => write!(f, "email address is not valid"),
+      Self::InvalidPhone => write!(f, "phone number is not valid"),
+      Self::DuplicateDocument => write!(f, "a passenger with this document exists"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait CreatePassenger {
+  /// Stores the passenger with its document number normalized.
+  fn create_passenger(&mut self, form: Passenger) -> Result<(), Error>;
+}
+
+impl CreatePassenger for Database {
+  fn create_passenger(&mut self, mut form: Passenger) -> Result<(), Error> {
+    if self.passengers.iter().any(|p| p.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    if form.name.trim().is_empty() {
+      return Err(Error::EmptyName);
+    }
+    if !form.document.is_valid() {
+      return Err(Error::InvalidDocument);
+    }
+    if !form.nationality.is_valid() {
+      return Err(Error::InvalidNationality);
+    }
+    if !form
+      .contact
+      .email
+      .as_deref()
+      .is_none_or(Contact::is_valid_email)
+    {
+      return Err(Error::InvalidEmail);
+    }
+    if !form
+      .contact
+      .phone
+      .as_deref()
+      .is_none_or(Contact::is_valid_phone)
+    {
+      return Err(Error::InvalidPhone);
+    }
+    if self.find_passenger_by_document(&form.document).is_some() {
+      return Err(Error::DuplicateDocument);
+    }
+
+    form.document.number = form.document.normalized_number();
+    self.passengers.push(form);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      fixtures::passenger,
+      passenger::{self, Country},
+    },
+  };
+
+  #[test]
+  fn normalizes_document() {
+    let mut database = Database::default();
+
+    assert_eq!(database.create_passenger(passenger()), Ok(()));
+    assert_eq!(database.passengers[0].document.number, "123456785");
+    assert_eq!(
+      database.create_passenger(Passenger {
+        id: passenger::Id(1),
+        ..passenger()
+      }),
+      Err(Error::DuplicateDocument)
+    );
+  }
+
+  #[test]
+  fn duplicate_id() {
+    let mut database = Database::default();
+    let mut other = passenger();
+    other.document.number = "11.111.111-1".to_string();
+
+    assert_eq!(database.create_passenger(passenger()), Ok(()));
+    assert_eq!(database.create_passenger(other), Err(Error::DuplicateId));
+    assert_eq!(database.passengers.len(), 1);
+  }
+
+  #[test]
+  fn validates_fields() {
+    let mut database = Database::default();
+    let mut invalid = passenger();
+    invalid.document.number = "12.345.678-0".to_string();
+
+    assert_eq!(
+      database.create_passenger(invalid),
+      Err(Error::InvalidDocument)
+    );
+    assert_eq!(
+      database.create_passenger(Passenger {
+        nationality: Country(*b"cl"),
+        ..passenger()
+      }),
+      Err(Error::InvalidNationality)
+    );
+    assert_eq!(
+      database.create_passenger(Passenger {
+        contact: Contact {
+          email: None,
+          phone: Some("call me".to_string()),
+        },
+        ..passenger()
+      }),
+      Err(Error::InvalidPhone)
+    );
+    assert!(database.passengers.is_empty());
+  }
+}
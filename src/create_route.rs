@@ -1,20 +1,173 @@
-use crate::{route::{self, Route}, Database};
-
+use {
+  crate::{
+    route::{self, Route},
+    Database,
+  },
+  std::{error, fmt::Display},
+};
 
 pub struct Form {
   pub stops: Vec<route::Stop>,
   pub route: Route,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  WrongRoute(route::StopId),
+  /// Stop indices must be exactly `0..stops.len()`.
+  InvalidIndices,
+  NonPositiveDistance(route::StopId),
+  NonPositiveRunningTime(route::StopId),
+  /// The stop is not reached `leg_running_time` after leaving the previous
+  /// one, or is left before it is reached.
+  InconsistentOffsets(route::StopId),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::WrongRoute(id) => write!(f, "route stop {} belongs to another route", id.0),
+      Self::InvalidIndices => write!(f, "route stop indices must be consecutive from 0"),
+      Self::NonPositiveDistance(id) => write!(f, "leg to route stop {} has no distance", id.0),
+      Self::NonPositiveRunningTime(id) => {
+        write!(f, "leg to route stop {} has no running time", id.0)
+      }
+      Self::InconsistentOffsets(id) => {
+        write!(f, "offsets of route stop {} do not match its leg", id.0)
+      }
+    }
+  }
+}
+impl error::Error for Error {}
+
 pub trait CreateRoute {
-  fn create_route(&mut self, form: Form);
+  fn create_route(&mut self, form: Form) -> Result<(), Error>;
 }
 
 impl CreateRoute for Database {
-  fn create_route(&mut self, form: Form) {
+  fn create_route(&mut self, form: Form) -> Result<(), Error> {
+    let mut indices: Vec<u16> = form.stops.iter().map(|s| s.index).collect();
+    indices.sort_unstable();
+    if !indices
+      .iter()
+      .enumerate()
+      .all(|(i, &index)| usize::from(index) == i)
+    {
+      return Err(Error::InvalidIndices);
+    }
+    for stop in &form.stops {
+      if stop.route_id != form.route.id {
+        return Err(Error::WrongRoute(stop.id));
+      }
+      if stop.index > 0 && stop.leg_distance_meters == 0 {
+        return Err(Error::NonPositiveDistance(stop.id));
+      }
+      if stop.index > 0 && stop.leg_running_time.is_zero() {
+        return Err(Error::NonPositiveRunningTime(stop.id));
+      }
+      if stop.departure_offset < stop.arrival_offset {
+        return Err(Error::InconsistentOffsets(stop.id));
+      }
+    }
+    let mut ordered: Vec<&route::Stop> = form.stops.iter().collect();
+    ordered.sort_by_key(|s| s.index);
+    for pair in ordered.windows(2) {
+      if pair[0]
+        .departure_offset
+        .checked_add(pair[1].leg_running_time)
+        != Some(pair[1].arrival_offset)
+      {
+        return Err(Error::InconsistentOffsets(pair[1].id));
+      }
+    }
+
     for stop in form.stops {
       self.route_stops.push(stop);
     }
     self.routes.push(form.route);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, std::time::Duration};
+
+  fn form() -> Form {
+    Form {
+      route: Route {
+        id: route::Id(0),
+        ..Default::default()
+      },
+      stops: vec![
+        route::Stop {
+          id: route::StopId(0),
+          ..Default::default()
+        },
+        route::Stop {
+          id: route::StopId(1),
+          index: 1,
+          arrival_offset: Duration::from_mins(70),
+          departure_offset: Duration::from_mins(70),
+          leg_distance_meters: 87_000,
+          leg_running_time: Duration::from_mins(70),
+          ..Default::default()
+        },
+      ],
+    }
+  }
+
+  #[test]
+  fn ok_test() {
+    let mut database: Database = Database::default();
+
+    assert_eq!(database.create_route(form()), Ok(()));
+    assert_eq!(database.route_stops.len(), 2);
+  }
+
+  #[test]
+  fn invalid_indices() {
+    let mut database: Database = Database::default();
+    let mut form = form();
+    form.stops[1].index = 2;
+
+    assert_eq!(database.create_route(form), Err(Error::InvalidIndices));
+  }
+
+  #[test]
+  fn non_positive_legs() {
+    let mut database: Database = Database::default();
+    let mut no_distance = form();
+    no_distance.stops[1].leg_distance_meters = 0;
+    let mut no_time = form();
+    no_time.stops[1].leg_running_time = Duration::ZERO;
+
+    assert_eq!(
+      database.create_route(no_distance),
+      Err(Error::NonPositiveDistance(route::StopId(1)))
+    );
+    assert_eq!(
+      database.create_route(no_time),
+      Err(Error::NonPositiveRunningTime(route::StopId(1)))
+    );
+    assert!(database.routes.is_empty());
+  }
+
+  #[test]
+  fn offsets_follow_running_times() {
+    let mut database: Database = Database::default();
+    let mut late = form();
+    late.stops[1].arrival_offset = Duration::from_mins(75);
+    let mut early_departure = form();
+    early_departure.stops[1].departure_offset = Duration::from_mins(65);
+
+    assert_eq!(
+      database.create_route(late),
+      Err(Error::InconsistentOffsets(route::StopId(1)))
+    );
+    assert_eq!(
+      database.create_route(early_departure),
+      Err(Error::InconsistentOffsets(route::StopId(1)))
+    );
   }
 }
@@ -0,0 +1,157 @@
+use {
+  crate::{schedule::Schedule, trip::Vehicle, Database},
+  std::{error, fmt::Display},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  RouteNotFound,
+  BusNotFound,
+  BusModelNotFound,
+  NoDays,
+  /// `valid_until` is before `valid_from`.
+  InvalidRange,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a schedule with this id exists"),
+      Self::RouteNotFound => write!(f, "route not found"),
+      Self::BusNotFound => write!(f, "bus not found"),
+      Self::BusModelNotFound => write!(f, "bus model not found"),
+      Self::NoDays => write!(f, "schedule runs on no day of the week"),
+      Self::InvalidRange => write!(f, "validity range ends before it starts"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait CreateSchedule {
+  fn create_schedule(&mut self, form: Schedule) -> Result<(), Error>;
+}
+
+impl CreateSchedule for Database {
+  fn create_schedule(&mut self, form: Schedule) -> Result<(), Error> {
+    if self.schedules.iter().any(|s| s.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    if !self.routes.iter().any(|r| r.id == form.route_id) {
+      return Err(Error::RouteNotFound);
+    }
+    match form.vehicle {
+      Vehicle::Bus(id) if !self.buses.iter().any(|b| b.id == id) => {
+        return Err(Error::BusNotFound);
+      }
+      Vehicle::BusModel(id) if !self.bus_models.iter().any(|m| m.id == id) => {
+        return Err(Error::BusModelNotFound);
+      }
+      _ => {}
+    }
+    if form.days.is_empty() {
+      return Err(Error::NoDays);
+    }
+    if form.valid_until < form.valid_from {
+      return Err(Error::InvalidRange);
+    }
+    self.schedules.push(form);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      bus::{self, Bus},
+      bus_model,
+      route::{self, Route},
+      schedule,
+      time::{Date, Weekday},
+    },
+  };
+
+  fn database() -> Database {
+    Database {
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      buses: vec![Bus {
+        id: bus::Id(0),
+        ..Default::default()
+      }],
+      ..Default::default()
+    }
+  }
+
+  fn form() -> Schedule {
+    Schedule {
+      id: schedule::Id(0),
+      days: vec![Weekday::Monday],
+      valid_from: Date::new(2026, 10, 1).unwrap_or_default(),
+      valid_until: Date::new(2026, 12, 31).unwrap_or_default(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn ok_test() {
+    let mut database: Database = database();
+
+    assert_eq!(database.create_schedule(form()), Ok(()));
+    assert_eq!(database.create_schedule(form()), Err(Error::DuplicateId));
+    assert_eq!(database.schedules.len(), 1);
+  }
+
+  #[test]
+  fn unknown_route_or_vehicle() {
+    let mut database: Database = database();
+
+    assert_eq!(
+      database.create_schedule(Schedule {
+        route_id: route::Id(1),
+        ..form()
+      }),
+      Err(Error::RouteNotFound)
+    );
+    assert_eq!(
+      database.create_schedule(Schedule {
+        vehicle: Vehicle::Bus(bus::Id(1)),
+        ..form()
+      }),
+      Err(Error::BusNotFound)
+    );
+    assert_eq!(
+      database.create_schedule(Schedule {
+        vehicle: Vehicle::BusModel(bus_model::Id(1)),
+        ..form()
+      }),
+      Err(Error::BusModelNotFound)
+    );
+  }
+
+  #[test]
+  fn invalid_calendar() {
+    let mut database: Database = database();
+
+    assert_eq!(
+      database.create_schedule(Schedule {
+        days: vec![],
+        ..form()
+      }),
+      Err(Error::NoDays)
+    );
+    assert_eq!(
+      database.create_schedule(Schedule {
+        valid_until: Date::new(2026, 9, 30).unwrap_or_default(),
+        ..form()
+      }),
+      Err(Error::InvalidRange)
+    );
+    assert!(database.schedules.is_empty());
+  }
+}
@@ -1,12 +1,129 @@
-use crate::{stop, Database};
+use {
+  crate::{geo::Coordinates, show_stop::ShowStop, stop, Database},
+  std::{error, fmt::Display},
+};
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  InvalidCoordinates,
+  ParentNotFound,
+  NestedPlatform,
+  /// The stop names itself as its station.
+  OwnParent,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a stop with this id exists"),
+      Self::InvalidCoordinates => write!(f, "coordinates out of range"),
+      Self::ParentNotFound => write!(f, "parent station not found"),
+      Self::NestedPlatform => write!(f, "a platform cannot contain platforms"),
+      Self::OwnParent => write!(f, "a stop cannot be a platform of itself"),
+    }
+  }
+}
+impl error::Error for Error {}
 
 pub trait CreateStop {
-  fn create_stop(&mut self, form: stop::Stop);
+  fn create_stop(&mut self, form: stop::Stop) -> Result<(), Error>;
 }
 
 impl CreateStop for Database {
-  fn create_stop(&mut self, form: stop::Stop) {
+  fn create_stop(&mut self, form: stop::Stop) -> Result<(), Error> {
+    // The spatial index is keyed by id, so a second stop would move the first.
+    if self.stops.iter().any(|s| s.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    if let Some(parent_id) = form.parent_id {
+      if parent_id == form.id {
+        return Err(Error::OwnParent);
+      }
+      let parent = self.show_stop(parent_id).ok_or(Error::ParentNotFound)?;
+      if parent.parent_id.is_some() {
+        return Err(Error::NestedPlatform);
+      }
+    }
+    if let Some(location) = form.location {
+      Coordinates::new(location.latitude, location.longitude).ok_or(Error::InvalidCoordinates)?;
+      self.stop_locations.insert(form.id, location);
+    }
     self.stops.push(form);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn duplicate_id() {
+    let mut database: Database = Database::default();
+    let stop = |latitude: f64| stop::Stop {
+      location: Some(Coordinates {
+        latitude,
+        longitude: 0.0,
+      }),
+      ..Default::default()
+    };
+
+    assert_eq!(database.create_stop(stop(10.0)), Ok(()));
+    assert_eq!(database.create_stop(stop(20.0)), Err(Error::DuplicateId));
+    assert_eq!(database.stops.len(), 1);
+    assert_eq!(
+      database
+        .stop_locations
+        .within(
+          Coordinates {
+            latitude: 10.0,
+            longitude: 0.0,
+          },
+          1.0
+        )
+        .len(),
+      1
+    );
+  }
+
+  #[test]
+  fn invalid_coordinates() {
+    let mut database: Database = Database::default();
+    let form = stop::Stop {
+      location: Some(Coordinates {
+        latitude: 100.0,
+        longitude: 0.0,
+      }),
+      ..Default::default()
+    };
+
+    assert_eq!(database.create_stop(form), Err(Error::InvalidCoordinates));
+    assert!(database.stops.is_empty());
+  }
+
+  #[test]
+  fn platforms_belong_to_stations() {
+    let mut database: Database = Database::default();
+    let stop = |id: u64, parent_id: Option<u64>| stop::Stop {
+      id: stop::Id(id),
+      parent_id: parent_id.map(stop::Id),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.create_stop(stop(1, Some(0))),
+      Err(Error::ParentNotFound)
+    );
+    assert_eq!(database.create_stop(stop(0, None)), Ok(()));
+    assert_eq!(database.create_stop(stop(1, Some(0))), Ok(()));
+    assert_eq!(
+      database.create_stop(stop(2, Some(1))),
+      Err(Error::NestedPlatform)
+    );
+    assert_eq!(
+      database.create_stop(stop(2, Some(2))),
+      Err(Error::OwnParent)
+    );
   }
 }
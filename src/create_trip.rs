@@ -1,16 +1,99 @@
-use crate::{trip::Trip, Database};
-
+use {
+  crate::{
+    bus_assignment::{CheckBusAssignment, Conflict},
+    trip::Trip,
+    Database,
+  },
+  std::{error, fmt::Display, time::Duration},
+};
 
 pub struct Form {
   pub trip: Trip,
+  /// Minimum time the bus needs between arriving and departing again.
+  pub turnaround: Duration,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  DuplicateId,
+  BusUnavailable(Conflict),
 }
 
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DuplicateId => write!(f, "a trip with this id exists"),
+      Self::BusUnavailable(conflict) => write!(f, "bus unavailable: {conflict:?}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
 pub trait CreateTrip {
-  fn create_trip(&mut self, form: Form);
+  fn create_trip(&mut self, form: Form) -> Result<(), Error>;
 }
 
 impl CreateTrip for Database {
-  fn create_trip(&mut self, form: Form) {
+  fn create_trip(&mut self, form: Form) -> Result<(), Error> {
+    // The bus check skips the trip's own id, so it must be new.
+    if self.trips.iter().any(|t| t.id == form.trip.id) {
+      return Err(Error::DuplicateId);
+    }
+    self
+      .check_bus_assignment(&form.trip, form.turnaround)
+      .map_err(Error::BusUnavailable)?;
     self.trips.push(form.trip);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{bus, bus_assignment::ConflictKind, time::Timestamp, trip},
+  };
+
+  #[test]
+  fn rejects_double_booked_bus() {
+    let mut database: Database = Database::default();
+    let form = |id: u64, departure: i64| Form {
+      trip: Trip {
+        id: trip::Id(id),
+        departure: Timestamp(departure),
+        ..Default::default()
+      },
+      turnaround: Duration::from_mins(15),
+    };
+
+    assert_eq!(database.create_trip(form(0, 0)), Ok(()));
+    assert_eq!(
+      database.create_trip(form(1, 600)),
+      Err(Error::BusUnavailable(Conflict {
+        bus_id: bus::Id(0),
+        earlier: trip::Id(0),
+        later: trip::Id(1),
+        kind: ConflictKind::Overlap,
+      }))
+    );
+    assert_eq!(database.create_trip(form(2, 900)), Ok(()));
+    assert_eq!(database.trips.len(), 2);
+  }
+
+  #[test]
+  fn duplicate_id() {
+    let mut database: Database = Database::default();
+    let form = |departure: i64| Form {
+      trip: Trip {
+        id: trip::Id(0),
+        departure: Timestamp(departure),
+        ..Default::default()
+      },
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(database.create_trip(form(0)), Ok(()));
+    assert_eq!(database.create_trip(form(600)), Err(Error::DuplicateId));
+    assert_eq!(database.trips.len(), 1);
   }
 }
@@ -0,0 +1,11 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// An account that buys tickets, possibly for other passengers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Customer {
+  pub id: Id,
+  pub name: String,
+  /// Login and receipt address, unique across customers.
+  pub email: String,
+}
@@ -0,0 +1,311 @@
+use {
+  crate::{
+    create_route::{self, CreateRoute},
+    route::{self, Route},
+    show_route::ShowRoute,
+    stop, Database,
+  },
+  std::{error, fmt::Display, time::Duration},
+};
+
+pub struct Form {
+  pub base_id: route::Id,
+  /// Identity of the variant. Its `base_id` is set from the form.
+  pub route: Route,
+  /// Indices of base route stops the variant does not serve.
+  pub skip: Vec<u16>,
+  /// Stops served after the last kept stop of the base route.
+  pub append: Vec<AppendedStop>,
+}
+
+pub struct AppendedStop {
+  pub stop_id: stop::Id,
+  pub leg_distance_meters: u32,
+  pub leg_running_time: Duration,
+  pub dwell: Duration,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  BaseNotFound,
+  InvalidSkip(u16),
+  TooFewStops,
+  TooManyStops,
+  DistanceOverflow,
+  DurationOverflow,
+  /// Every route stop id has been handed out.
+  IdsExhausted,
+  InvalidRoute(create_route::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::BaseNotFound => write!(f, "base route not found"),
+      Self::InvalidSkip(index) => write!(f, "base route has no stop {index}"),
+      Self::TooFewStops => write!(f, "a route needs at least two stops"),
+      Self::TooManyStops => write!(f, "a route has at most {} stops", u16::MAX),
+      Self::DistanceOverflow => write!(f, "leg distance is too long"),
+      Self::DurationOverflow => write!(f, "running time is too long"),
+      Self::IdsExhausted => write!(f, "no route stop id is left"),
+      Self::InvalidRoute(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait DeriveRoute {
+  /// Creates a variant of an existing route. Skipped stops fold their legs
+  /// into the next served stop, and offsets are rebuilt from running times
+  /// and the dwell kept at each stop, so the first served stop departs at 0.
+  fn derive_route(&mut self, form: Form) -> Result<(), Error>;
+}
+
+impl DeriveRoute for Database {
+  fn derive_route(&mut self, form: Form) -> Result<(), Error> {
+    self.show_route(form.base_id).ok_or(Error::BaseNotFound)?;
+    let mut base: Vec<&route::Stop> = self
+      .route_stops
+      .iter()
+      .filter(|rs| rs.route_id == form.base_id)
+      .collect();
+    base.sort_by_key(|rs| rs.index);
+    if let Some(&index) = form.skip.iter().find(|&&i| usize::from(i) >= base.len()) {
+      return Err(Error::InvalidSkip(index));
+    }
+
+    // (stop, leg distance, leg running time, dwell)
+    let mut served: Vec<(stop::Id, u32, Duration, Duration)> = vec![];
+    let (mut distance, mut running_time) = (0_u32, Duration::ZERO);
+    for rs in base {
+      distance = distance
+        .checked_add(rs.leg_distance_meters)
+        .ok_or(Error::DistanceOverflow)?;
+      running_time = running_time
+        .checked_add(rs.leg_running_time)
+        .ok_or(Error::DurationOverflow)?;
+      if form.skip.contains(&rs.index) {
+        continue;
+      }
+      let dwell = if served.is_empty() {
+        (distance, running_time) = (0, Duration::ZERO);
+        Duration::ZERO
+      } else {
+        rs.departure_offset.saturating_sub(rs.arrival_offset)
+      };
+      served.push((rs.stop_id, distance, running_time, dwell));
+      (distance, running_time) = (0, Duration::ZERO);
+    }
+    served.extend(form.append.iter().map(|s| {
+      (
+        s.stop_id,
+        s.leg_distance_meters,
+        s.leg_running_time,
+        s.dwell,
+      )
+    }));
+    if served.len() < 2 {
+      return Err(Error::TooFewStops);
+    }
+
+    let next_id = self
+      .route_stops
+      .iter()
+      .try_fold(0, |next: u64, rs| {
+        rs.id.0.checked_add(1).map(|id| next.max(id))
+      })
+      .ok_or(Error::IdsExhausted)?;
+    let mut departure = Duration::ZERO;
+    let mut stops: Vec<route::Stop> = vec![];
+    for (i, (stop_id, leg_distance_meters, leg_running_time, dwell)) in
+      served.into_iter().enumerate()
+    {
+      let arrival_offset = departure
+        .checked_add(leg_running_time)
+        .ok_or(Error::DurationOverflow)?;
+      departure = arrival_offset
+        .checked_add(dwell)
+        .ok_or(Error::DurationOverflow)?;
+      stops.push(route::Stop {
+        id: u64::try_from(i)
+          .ok()
+          .and_then(|i| next_id.checked_add(i))
+          .map(route::StopId)
+          .ok_or(Error::IdsExhausted)?,
+        stop_id,
+        route_id: form.route.id,
+        index: u16::try_from(i).map_err(|_| Error::TooManyStops)?,
+        arrival_offset,
+        departure_offset: departure,
+        leg_distance_meters,
+        leg_running_time,
+      });
+    }
+
+    self
+      .create_route(create_route::Form {
+        stops,
+        route: Route {
+          base_id: Some(form.base_id),
+          ..form.route
+        },
+      })
+      .map_err(Error::InvalidRoute)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Four stops 10 km and 10 minutes apart, with two minute dwells in the
+  /// middle.
+  fn database() -> Database {
+    Database {
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: (0..4)
+        .map(|i: u16| {
+          let minutes = u64::from(i) * 12;
+          route::Stop {
+            id: route::StopId(u64::from(i)),
+            stop_id: stop::Id(u64::from(i)),
+            route_id: route::Id(0),
+            index: i,
+            arrival_offset: Duration::from_mins(minutes.saturating_sub(2)),
+            departure_offset: Duration::from_mins(minutes),
+            leg_distance_meters: if i == 0 { 0 } else { 10_000 },
+            leg_running_time: if i == 0 {
+              Duration::ZERO
+            } else {
+              Duration::from_mins(10)
+            },
+          }
+        })
+        .collect(),
+      ..Default::default()
+    }
+  }
+
+  fn variant(database: &Database) -> Vec<(u64, u32, u64, u64)> {
+    database
+      .route_stops
+      .iter()
+      .filter(|rs| rs.route_id == route::Id(1))
+      .map(|rs| {
+        (
+          rs.stop_id.0,
+          rs.leg_distance_meters,
+          rs.arrival_offset.as_secs() / 60,
+          rs.departure_offset.as_secs() / 60,
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn express_skips_stops() {
+    let mut database = database();
+    let form = Form {
+      base_id: route::Id(0),
+      route: Route {
+        id: route::Id(1),
+        code: "1X".to_string(),
+        ..Default::default()
+      },
+      skip: vec![1, 2],
+      append: vec![],
+    };
+
+    assert_eq!(database.derive_route(form), Ok(()));
+    assert_eq!(variant(&database), vec![(0, 0, 0, 0), (3, 30_000, 30, 32)]);
+    assert_eq!(database.routes[1].base_id, Some(route::Id(0)));
+  }
+
+  #[test]
+  fn different_terminus() {
+    let mut database = database();
+    let form = Form {
+      base_id: route::Id(0),
+      route: Route {
+        id: route::Id(1),
+        ..Default::default()
+      },
+      skip: vec![0, 3],
+      append: vec![AppendedStop {
+        stop_id: stop::Id(9),
+        leg_distance_meters: 5_000,
+        leg_running_time: Duration::from_mins(7),
+        dwell: Duration::ZERO,
+      }],
+    };
+
+    assert_eq!(database.derive_route(form), Ok(()));
+    assert_eq!(
+      variant(&database),
+      vec![(1, 0, 0, 0), (2, 10_000, 10, 12), (9, 5_000, 19, 19)]
+    );
+  }
+
+  #[test]
+  fn invalid_forms() {
+    let mut database = database();
+    let form = |base: u64, skip: Vec<u16>| Form {
+      base_id: route::Id(base),
+      route: Route {
+        id: route::Id(1),
+        ..Default::default()
+      },
+      skip,
+      append: vec![],
+    };
+
+    assert_eq!(
+      database.derive_route(form(5, vec![])),
+      Err(Error::BaseNotFound)
+    );
+    assert_eq!(
+      database.derive_route(form(0, vec![4])),
+      Err(Error::InvalidSkip(4))
+    );
+    assert_eq!(
+      database.derive_route(form(0, vec![0, 1, 2])),
+      Err(Error::TooFewStops)
+    );
+
+    database.route_stops[1].leg_distance_meters = u32::MAX;
+    assert_eq!(
+      database.derive_route(form(0, vec![1])),
+      Err(Error::DistanceOverflow)
+    );
+
+    database.route_stops[1].leg_distance_meters = 10_000;
+    database.route_stops[2].leg_running_time = Duration::MAX;
+    assert_eq!(
+      database.derive_route(form(0, vec![1])),
+      Err(Error::DurationOverflow)
+    );
+    database.route_stops[2].leg_running_time = Duration::from_mins(10);
+    assert_eq!(
+      database.derive_route(Form {
+        append: vec![AppendedStop {
+          stop_id: stop::Id(9),
+          leg_distance_meters: 0,
+          leg_running_time: Duration::MAX,
+          dwell: Duration::ZERO,
+        }],
+        ..form(0, vec![])
+      }),
+      Err(Error::DurationOverflow)
+    );
+    database.route_stops[3].id = route::StopId(u64::MAX);
+    assert_eq!(
+      database.derive_route(form(0, vec![])),
+      Err(Error::IdsExhausted)
+    );
+    assert!(database.routes.iter().all(|r| r.id == route::Id(0)));
+  }
+}
@@ -0,0 +1,22 @@
+use crate::money::{Exact, Money};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Discount {
+  /// Share of the price, capped at 100.
+  Percent(u8),
+  /// Never more than the price. Only applies to prices in its currency, or
+  /// to a quote converted from the route's currency when it is in that one.
+  Fixed(Money),
+}
+
+impl Discount {
+  /// Exact amount taken off `price`. A fixed amount is first brought into
+  /// `price`'s currency by `convert`, which gives `None` when it cannot be.
+  pub fn exact_off(self, price: Exact, convert: impl Fn(Money) -> Option<Exact>) -> Option<Exact> {
+    match self {
+      Self::Percent(percent) => price.scale(u64::from(percent.min(100)), 100),
+      Self::Fixed(amount) => convert(amount)?.min(price),
+    }
+  }
+}
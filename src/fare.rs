@@ -0,0 +1,99 @@
+use {
+  crate::money::{Currency, Exact, Money},
+  std::time::Duration,
+};
+
+/// Prices in integer minor units of the route's currency.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Fare {
+  /// Explicit price for each origin–destination pair of stop indices.
+  Matrix(Vec<OdFare>),
+  /// `base + per_km × distance`, with the distance taken from the route's
+  /// leg lengths.
+  Distance { base: u64, per_km: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OdFare {
+  pub from: u16,
+  pub to: u16,
+  pub amount: u64,
+}
+
+impl Fare {
+  /// Exact price in `currency`, the route's, left unrounded.
+  pub fn price(
+    &self,
+    from: usize,
+    to: usize,
+    distance_meters: u64,
+    currency: Currency,
+  ) -> Option<Exact> {
+    match self {
+      Self::Matrix(fares) => fares
+        .iter()
+        .find(|f| usize::from(f.from) == from && usize::from(f.to) == to)
+        .map(|f| Money::new(f.amount, currency).into()),
+      Self::Distance { base, per_km } => {
+        let variable = Exact::from(Money::new(*per_km, currency)).scale(distance_meters, 1_000)?;
+        Exact::from(Money::new(*base, currency)).checked_add(variable)
+      }
+    }
+  }
+}
+
+/// Adjustments applied on top of the base fare. Percentages are of the base,
+/// so 100 leaves it unchanged; an empty policy never adjusts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PricingPolicy {
+  pub load_tiers: Vec<LoadTier>,
+  pub departure_tiers: Vec<DepartureTier>,
+}
+
+/// Applies once the segment load reaches `min_load_percent`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadTier {
+  pub min_load_percent: u8,
+  pub price_percent: u16,
+}
+
+/// Applies when booking no earlier than `within` before departure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepartureTier {
+  pub within: Duration,
+  pub price_percent: u16,
+}
+
+impl PricingPolicy {
+  /// The highest tier the load has reached.
+  pub fn load_tier(&self, load_percent: u8) -> Option<LoadTier> {
+    self
+      .load_tiers
+      .iter()
+      .filter(|t| t.min_load_percent <= load_percent)
+      .max_by_key(|t| t.min_load_percent)
+      .copied()
+  }
+
+  /// The narrowest window the booking falls into.
+  pub fn departure_tier(&self, until_departure: Duration) -> Option<DepartureTier> {
+    self
+      .departure_tiers
+      .iter()
+      .filter(|t| until_departure <= t.within)
+      .min_by_key(|t| t.within)
+      .copied()
+  }
+
+  /// `base` scaled by both tiers.
+  pub fn apply(
+    base: Exact,
+    load_tier: Option<LoadTier>,
+    departure_tier: Option<DepartureTier>,
+  ) -> Option<Exact> {
+    let load = load_tier.map_or(100, |t| t.price_percent);
+    let departure = departure_tier.map_or(100, |t| t.price_percent);
+    base.scale(u64::from(load) * u64::from(departure), 10_000)
+  }
+}
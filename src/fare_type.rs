@@ -0,0 +1,12 @@
+use crate::discount::Discount;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// A passenger category with a reduced fare, e.g. students or seniors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FareType {
+  pub id: Id,
+  pub name: String,
+  pub discount: Discount,
+}
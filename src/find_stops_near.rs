@@ -0,0 +1,67 @@
+use crate::{geo::Coordinates, show_stop::ShowStop, stop, Database};
+
+#[allow(dead_code)]
+pub trait FindStopsNear {
+  /// Stops within `radius` meters, closest first, with their distance.
+  fn find_stops_within(&self, center: Coordinates, radius: f64) -> Vec<(&stop::Stop, f64)>;
+
+  /// The `count` stops closest to `center`, closest first, with their
+  /// distance.
+  fn find_nearest_stops(&self, center: Coordinates, count: usize) -> Vec<(&stop::Stop, f64)>;
+}
+
+impl FindStopsNear for Database {
+  fn find_stops_within(&self, center: Coordinates, radius: f64) -> Vec<(&stop::Stop, f64)> {
+    self
+      .stop_locations
+      .within(center, radius)
+      .into_iter()
+      .filter_map(|(id, distance)| Some((self.show_stop(id)?, distance)))
+      .collect()
+  }
+
+  fn find_nearest_stops(&self, center: Coordinates, count: usize) -> Vec<(&stop::Stop, f64)> {
+    self
+      .stop_locations
+      .nearest(center, count)
+      .into_iter()
+      .filter_map(|(id, distance)| Some((self.show_stop(id)?, distance)))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::create_stop::CreateStop};
+
+  #[test]
+  fn departures_near_me() {
+    let mut database: Database = Database::default();
+    let stop = |id: u64, latitude: f64, longitude: f64| stop::Stop {
+      id: stop::Id(id),
+      location: Coordinates::new(latitude, longitude),
+      ..Default::default()
+    };
+    let _ = database.create_stop(stop(0, -33.4569, -70.6483));
+    let _ = database.create_stop(stop(1, -33.0472, -71.6127));
+    let _ = database.create_stop(stop::Stop {
+      id: stop::Id(2),
+      ..Default::default()
+    });
+    let here = Coordinates {
+      latitude: -33.45,
+      longitude: -70.66,
+    };
+
+    let ids = |found: Vec<(&stop::Stop, f64)>| found.iter().map(|(s, _)| s.id).collect::<Vec<_>>();
+
+    assert_eq!(
+      ids(database.find_stops_within(here, 2_000.0)),
+      vec![stop::Id(0)]
+    );
+    assert_eq!(
+      ids(database.find_nearest_stops(here, 5)),
+      vec![stop::Id(0), stop::Id(1)]
+    );
+  }
+}
@@ -0,0 +1,239 @@
+//! Test databases shared by the tests of several operations.
+
+use {
+  crate::{
+    bus::{self, Bus},
+    bus_model::{self, BusModel},
+    customer::{self, Customer},
+    fare::{Fare, OdFare},
+    order::{self, Order},
+    passenger::{self, Contact, Country, Document, DocumentKind, Passenger},
+    payment,
+    payment_provider::FakeProvider,
+    place_order::{self, PlaceOrder},
+    process_payment::{self, ProcessPayment},
+    reserve_seat,
+    route::{self, Route},
+    seat_layout::{Class, ClassId, Position, Seat, SeatLayout},
+    stop,
+    time::Timestamp,
+    trip::{self, Trip},
+    Database,
+  },
+  std::time::Duration,
+};
+
+pub const CAMA: ClassId = ClassId(0);
+pub const SEMI_CAMA: ClassId = ClassId(1);
+
+/// A two stop trip on a single seat bus.
+pub fn database() -> Database {
+  Database {
+    trips: vec![Trip {
+      id: trip::Id(0),
+      route_id: route::Id(0),
+      ..Default::default()
+    }],
+    routes: vec![Route {
+      id: route::Id(0),
+      ..Default::default()
+    }],
+    route_stops: vec![
+      route::Stop {
+        route_id: route::Id(0),
+        ..Default::default()
+      },
+      route::Stop {
+        route_id: route::Id(0),
+        index: 1,
+        ..Default::default()
+      },
+    ],
+    buses: vec![Bus {
+      id: bus::Id(0),
+      bus_model_id: bus_model::Id(0),
+    }],
+    bus_models: vec![BusModel {
+      id: bus_model::Id(0),
+      number_of_seats: 1,
+      ..Default::default()
+    }],
+    ..Database::default()
+  }
+}
+
+/// A three stop trip on a bus with two "cama" seats followed by three
+/// "semi-cama" ones.
+pub fn class_database() -> Database {
+  let seat = |i: u8, class_id: ClassId| Seat {
+    label: i.to_string(),
+    position: Position {
+      row: i,
+      ..Default::default()
+    },
+    class_id: Some(class_id),
+    ..Default::default()
+  };
+  Database {
+    trips: vec![Trip {
+      id: trip::Id(0),
+      route_id: route::Id(0),
+      ..Default::default()
+    }],
+    routes: vec![Route {
+      id: route::Id(0),
+      ..Default::default()
+    }],
+    route_stops: (0..3)
+      .map(|index| route::Stop {
+        route_id: route::Id(0),
+        index,
+        ..Default::default()
+      })
+      .collect(),
+    buses: vec![Bus {
+      id: bus::Id(0),
+      bus_model_id: bus_model::Id(0),
+    }],
+    bus_models: vec![BusModel {
+      id: bus_model::Id(0),
+      number_of_seats: 5,
+      layout: Some(SeatLayout {
+        decks: 1,
+        classes: vec![
+          Class {
+            id: CAMA,
+            name: "Cama".to_string(),
+          },
+          Class {
+            id: SEMI_CAMA,
+            name: "Semi-cama".to_string(),
+          },
+        ],
+        seats: vec![
+          seat(0, CAMA),
+          seat(1, CAMA),
+          seat(2, SEMI_CAMA),
+          seat(3, SEMI_CAMA),
+          seat(4, SEMI_CAMA),
+        ],
+        fixtures: vec![],
+      }),
+      ..Default::default()
+    }],
+    ..Default::default()
+  }
+}
+
+/// "Terminal Sur" (0) with bays 1 and 2, plus a standalone stop (3).
+pub fn stops() -> Vec<stop::Stop> {
+  let stop = |id: u64, name: &str, parent_id: Option<u64>| stop::Stop {
+    id: stop::Id(id),
+    name: name.to_string(),
+    parent_id: parent_id.map(stop::Id),
+    ..Default::default()
+  };
+  vec![
+    stop(0, "Terminal Sur", None),
+    stop(1, "Terminal Sur Andén 1", Some(0)),
+    stop(2, "Terminal Sur Andén 2", Some(0)),
+    stop(3, "Rancagua", None),
+  ]
+}
+
+/// Three stops 40 km and 60 km apart, with a five minute stop in between.
+pub fn route_stops() -> Vec<route::Stop> {
+  vec![
+    route::Stop {
+      id: route::StopId(0),
+      ..Default::default()
+    },
+    route::Stop {
+      id: route::StopId(2),
+      index: 2,
+      leg_distance_meters: 60_000,
+      leg_running_time: Duration::from_mins(50),
+      ..Default::default()
+    },
+    route::Stop {
+      id: route::StopId(1),
+      index: 1,
+      arrival_offset: Duration::from_mins(30),
+      departure_offset: Duration::from_mins(35),
+      leg_distance_meters: 40_000,
+      leg_running_time: Duration::from_mins(30),
+      ..Default::default()
+    },
+  ]
+}
+
+pub fn passenger() -> Passenger {
+  Passenger {
+    id: passenger::Id(0),
+    name: "Ana Rojas".to_string(),
+    document: Document {
+      kind: DocumentKind::Rut,
+      number: "12.345.678-5".to_string(),
+    },
+    nationality: Country(*b"CL"),
+    contact: Contact {
+      email: Some("ana@example.cl".to_string()),
+      phone: None,
+    },
+    attributes: vec![],
+  }
+}
+
+/// Two priced trips on a two seat bus and a customer with no orders.
+pub fn order_database() -> Database {
+  let mut database = database();
+  database.bus_models[0].number_of_seats = 2;
+  database.trips.push(Trip {
+    id: trip::Id(1),
+    ..database.trips[0]
+  });
+  database.routes[0].fare = Some(Fare::Matrix(vec![OdFare {
+    from: 0,
+    to: 1,
+    amount: 1_000,
+  }]));
+  database.customers = vec![Customer {
+    id: customer::Id(0),
+    ..Default::default()
+  }];
+  database
+}
+
+pub fn item(trip_id: u64, seat_index: usize) -> reserve_seat::Form {
+  reserve_seat::Form {
+    seat_index,
+    to_stop_index: 1,
+    trip_id: trip::Id(trip_id),
+    ..Default::default()
+  }
+}
+
+/// A held, priced order over both test trips.
+pub fn ordered() -> Database {
+  let mut database = order_database();
+  let _ = database.place_order(place_order::Form {
+    order: Order::default(),
+    items: vec![item(0, 0), item(1, 0)],
+  });
+  let _ = database.create_payment(process_payment::Form {
+    id: payment::Id(0),
+    order_id: order::Id(0),
+  });
+  database
+}
+
+/// The order paid for, with both trips departing at 100 000.
+pub fn ticketed(provider: &mut FakeProvider) -> Database {
+  let mut database = ordered();
+  for trip in &mut database.trips {
+    trip.departure = Timestamp(100_000);
+  }
+  let _ = database.authorize_payment(payment::Id(0), provider);
+  let _ = database.capture_payment(payment::Id(0), provider);
+  database
+}
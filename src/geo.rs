@@ -0,0 +1,240 @@
+//! Coordinates and a grid index for "stops near me" queries.
+
+use {crate::stop, std::collections::HashMap};
+
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+const METERS_PER_DEGREE: f64 = 111_195.0;
+/// Half the circumference: no two points are further apart.
+const MAX_DISTANCE_METERS: f64 = 20_015_087.0;
+/// Edge of a grid cell in degrees, roughly 11 km of latitude.
+const CELL_DEGREES: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Coordinates {
+  pub latitude: f64,
+  pub longitude: f64,
+}
+
+impl Coordinates {
+  pub fn new(latitude: f64, longitude: f64) -> Option<Self> {
+    let valid = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+    valid.then_some(Self {
+      latitude,
+      longitude,
+    })
+  }
+
+  /// Great-circle distance in meters.
+  pub fn distance(self, other: Self) -> f64 {
+    let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (other.longitude - self.longitude).to_radians();
+    let a =
+      (lat1.cos() * lat2.cos()).mul_add((d_lon / 2.0).sin().powi(2), (d_lat / 2.0).sin().powi(2));
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+  }
+
+  #[allow(clippy::cast_possible_truncation)]
+  fn cell(self) -> (i32, i32) {
+    // 180 and -180 are the same meridian; keep it in the cell lookups wrap to.
+    let longitude = if self.longitude >= 180.0 {
+      -180.0
+    } else {
+      self.longitude
+    };
+    (
+      (self.latitude / CELL_DEGREES).floor() as i32,
+      (longitude / CELL_DEGREES).floor() as i32,
+    )
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct SpatialIndex {
+  cells: HashMap<(i32, i32), Vec<(stop::Id, Coordinates)>>,
+}
+
+impl SpatialIndex {
+  pub fn insert(&mut self, id: stop::Id, coordinates: Coordinates) {
+    self
+      .cells
+      .entry(coordinates.cell())
+      .or_default()
+      .push((id, coordinates));
+  }
+
+  #[allow(dead_code)]
+  pub fn remove(&mut self, id: stop::Id) {
+    for entries in self.cells.values_mut() {
+      entries.retain(|(entry, _)| *entry != id);
+    }
+  }
+
+  /// Entries within `radius` meters of `center`, closest first.
+  #[allow(clippy::cast_possible_truncation)]
+  pub fn within(&self, center: Coordinates, radius: f64) -> Vec<(stop::Id, f64)> {
+    let lat_span = radius / METERS_PER_DEGREE;
+    let lon_span = lat_span / center.latitude.to_radians().cos().max(0.01);
+    let cell_span = |span: f64| (span / CELL_DEGREES).ceil().min(1_800.0) as i32 + 1;
+    let (lat_cell, lon_cell) = center.cell();
+    let (lat_cells, lon_cells) = (cell_span(lat_span), cell_span(lon_span));
+
+    let grid_cells = (2 * i64::from(lat_cells) + 1) * (2 * i64::from(lon_cells) + 1);
+    let candidates: Vec<&(stop::Id, Coordinates)> =
+      if usize::try_from(grid_cells).map_or(true, |n| n > self.cells.len()) {
+        // Scanning the populated cells is cheaper than walking the grid.
+        self.cells.values().flatten().collect()
+      } else {
+        let mut candidates: Vec<&(stop::Id, Coordinates)> = vec![];
+        for lat in lat_cell - lat_cells..=lat_cell + lat_cells {
+          for lon in lon_cell - lon_cells..=lon_cell + lon_cells {
+            // Longitude cells wrap around the antimeridian.
+            let lon = (lon + 1_800).rem_euclid(3_600) - 1_800;
+            candidates.extend(self.cells.get(&(lat, lon)).into_iter().flatten());
+          }
+        }
+        candidates.sort_by_key(|(id, _)| id.0);
+        candidates.dedup_by_key(|(id, _)| id.0);
+        candidates
+      };
+
+    let mut found: Vec<(stop::Id, f64)> = candidates
+      .into_iter()
+      .map(|&(id, coordinates)| (id, center.distance(coordinates)))
+      .filter(|&(_, distance)| distance <= radius)
+      .collect();
+    found.sort_by(|a, b| a.1.total_cmp(&b.1));
+    found
+  }
+
+  /// The `count` entries closest to `center`, closest first.
+  pub fn nearest(&self, center: Coordinates, count: usize) -> Vec<(stop::Id, f64)> {
+    let mut radius = CELL_DEGREES * METERS_PER_DEGREE;
+    loop {
+      let mut found = self.within(center, radius);
+      if found.len() >= count || radius >= MAX_DISTANCE_METERS {
+        found.truncate(count);
+        return found;
+      }
+      radius = (radius * 4.0).min(MAX_DISTANCE_METERS);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SANTIAGO: Coordinates = Coordinates {
+    latitude: -33.4569,
+    longitude: -70.6483,
+  };
+  const VALPARAISO: Coordinates = Coordinates {
+    latitude: -33.0472,
+    longitude: -71.6127,
+  };
+
+  #[test]
+  fn validates_ranges() {
+    assert_eq!(
+      Coordinates::new(-33.0, -70.0),
+      Some(Coordinates {
+        latitude: -33.0,
+        longitude: -70.0
+      })
+    );
+    assert_eq!(Coordinates::new(91.0, 0.0), None);
+    assert_eq!(Coordinates::new(0.0, -181.0), None);
+  }
+
+  #[test]
+  fn distance() {
+    let meters = SANTIAGO.distance(VALPARAISO);
+
+    assert!((98_000.0..102_000.0).contains(&meters), "{meters}");
+    assert!(SANTIAGO.distance(SANTIAGO).abs() < f64::EPSILON);
+  }
+
+  #[test]
+  fn within_and_nearest() {
+    let mut index = SpatialIndex::default();
+    index.insert(stop::Id(0), SANTIAGO);
+    index.insert(stop::Id(1), VALPARAISO);
+    index.insert(
+      stop::Id(2),
+      Coordinates {
+        latitude: -33.4489,
+        longitude: -70.6693,
+      },
+    );
+
+    let ids = |found: Vec<(stop::Id, f64)>| found.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+    assert_eq!(
+      ids(index.within(SANTIAGO, 5_000.0)),
+      vec![stop::Id(0), stop::Id(2)]
+    );
+    assert_eq!(
+      ids(index.nearest(VALPARAISO, 2)),
+      vec![stop::Id(1), stop::Id(2)]
+    );
+    assert_eq!(ids(index.nearest(SANTIAGO, 9)).len(), 3);
+
+    index.remove(stop::Id(0));
+    assert_eq!(ids(index.within(SANTIAGO, 5_000.0)), vec![stop::Id(2)]);
+  }
+
+  #[test]
+  fn across_antimeridian() {
+    let mut index = SpatialIndex::default();
+    index.insert(
+      stop::Id(0),
+      Coordinates {
+        latitude: 0.0,
+        longitude: 179.99,
+      },
+    );
+
+    let found = index.within(
+      Coordinates {
+        latitude: 0.0,
+        longitude: -179.99,
+      },
+      5_000.0,
+    );
+
+    assert_eq!(found.len(), 1);
+  }
+
+  #[test]
+  fn on_antimeridian() {
+    let mut index = SpatialIndex::default();
+    // Enough populated cells that the query walks the grid.
+    for i in 0..30_u32 {
+      index.insert(
+        stop::Id(100 + u64::from(i)),
+        Coordinates {
+          latitude: 45.0,
+          longitude: f64::from(i),
+        },
+      );
+    }
+    index.insert(
+      stop::Id(0),
+      Coordinates {
+        latitude: 0.0,
+        longitude: 180.0,
+      },
+    );
+
+    let found = index.within(
+      Coordinates {
+        latitude: 0.0,
+        longitude: 179.99,
+      },
+      5_000.0,
+    );
+
+    assert_eq!(found.len(), 1);
+  }
+}
@@ -0,0 +1,58 @@
+use crate::{
+  seat_block::{self, SeatBlock},
+  Database,
+};
+
+#[allow(dead_code)]
+pub trait LiftSeatBlock {
+  /// Removes the block, returning it if it existed.
+  fn lift_seat_block(&mut self, id: seat_block::Id) -> Option<SeatBlock>;
+}
+
+impl LiftSeatBlock for Database {
+  fn lift_seat_block(&mut self, id: seat_block::Id) -> Option<SeatBlock> {
+    let index = self.seat_blocks.iter().position(|b| b.id == id)?;
+    Some(self.seat_blocks.remove(index))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      block_seat::{self, BlockSeat},
+      fixtures::database,
+      reserve_seat::{self, ReserveSeat},
+      seat_block::Reason,
+      trip,
+    },
+  };
+
+  #[test]
+  fn lifted_seat_can_be_reserved() {
+    let mut database = database();
+    let id = database
+      .block_seat(block_seat::Form {
+        trip_id: trip::Id(0),
+        seat_index: 0,
+        from_stop_index: 0,
+        to_stop_index: 1,
+        reason: Reason::TourGuide,
+      })
+      .unwrap_or_default();
+
+    assert_eq!(
+      database.lift_seat_block(id).map(|b| b.reason),
+      Some(Reason::TourGuide)
+    );
+    assert_eq!(database.lift_seat_block(id), None);
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        to_stop_index: 1,
+        ..Default::default()
+      }),
+      Ok(())
+    );
+  }
+}
@@ -0,0 +1,116 @@
+use crate::{customer, order::Order, seat_reservation::SeatReservation, Database};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct OrderHistoryEntry<'a> {
+  pub order: &'a Order,
+  pub reservations: Vec<&'a SeatReservation>,
+}
+
+#[allow(dead_code)]
+pub trait ListOrders {
+  /// Orders of the customer, most recent first, with their reservations.
+  fn list_customer_orders(&self, id: customer::Id) -> Vec<OrderHistoryEntry<'_>>;
+}
+
+impl ListOrders for Database {
+  fn list_customer_orders(&self, id: customer::Id) -> Vec<OrderHistoryEntry<'_>> {
+    let mut orders: Vec<&Order> = self.orders.iter().filter(|o| o.customer_id == id).collect();
+    orders.sort_by_key(|o| std::cmp::Reverse((o.placed_at, o.id.0)));
+
+    orders
+      .into_iter()
+      .map(|order| OrderHistoryEntry {
+        order,
+        reservations: self
+          .seat_reservations
+          .iter()
+          .filter(|sr| sr.order_id() == Some(order.id))
+          .collect(),
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      create_order::{self, CreateOrder},
+      customer::Customer,
+      fixtures::database,
+      order,
+      passenger::{self, Passenger},
+      reserve_seat::{self, ReserveSeat},
+      time::Timestamp,
+    },
+  };
+
+  #[test]
+  fn history_newest_first() {
+    let mut database = database();
+    database.bus_models[0].number_of_seats = 3;
+    database.customers = vec![Customer {
+      id: customer::Id(0),
+      ..Default::default()
+    }];
+    database.passengers = (0..2)
+      .map(|id| Passenger {
+        id: passenger::Id(id),
+        ..Default::default()
+      })
+      .collect();
+    for (id, placed_at) in [(0, 10), (1, 20)] {
+      let _ = database.create_order(Order {
+        id: order::Id(id),
+        customer_id: customer::Id(0),
+        placed_at: Timestamp(placed_at),
+        ..Default::default()
+      });
+    }
+    for (seat_index, passenger, order) in [(0, 0, 0), (1, 1, 0), (2, 0, 1)] {
+      let _ = database.reserve_seat(reserve_seat::Form {
+        seat_index,
+        to_stop_index: 1,
+        passenger_id: Some(passenger::Id(passenger)),
+        order_id: Some(order::Id(order)),
+        ..Default::default()
+      });
+    }
+    let history: Vec<(order::Id, Vec<Option<passenger::Id>>)> = database
+      .list_customer_orders(customer::Id(0))
+      .into_iter()
+      .map(|entry| {
+        let passengers = entry.reservations.iter().map(|sr| sr.passenger_id());
+        (entry.order.id, passengers.collect())
+      })
+      .collect();
+
+    assert_eq!(
+      history,
+      [
+        (order::Id(1), vec![Some(passenger::Id(0))]),
+        (
+          order::Id(0),
+          vec![Some(passenger::Id(0)), Some(passenger::Id(1))]
+        ),
+      ]
+    );
+    assert_eq!(
+      database.create_order(Order {
+        customer_id: customer::Id(5),
+        ..Default::default()
+      }),
+      Err(create_order::Error::CustomerNotFound)
+    );
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        to_stop_index: 1,
+        order_id: Some(order::Id(9)),
+        ..Default::default()
+      }),
+      Err(reserve_seat::Error::OrderNotFound)
+    );
+  }
+}
@@ -0,0 +1,78 @@
+use crate::{passenger, seat_reservation::SeatReservation, show_trip::ShowTrip, Database};
+
+#[allow(dead_code)]
+pub trait ListPassengerBookings {
+  /// Reservations of the passenger, ordered by trip departure.
+  fn list_passenger_bookings(&self, id: passenger::Id) -> Vec<&SeatReservation>;
+}
+
+impl ListPassengerBookings for Database {
+  fn list_passenger_bookings(&self, id: passenger::Id) -> Vec<&SeatReservation> {
+    let mut bookings: Vec<&SeatReservation> = self
+      .seat_reservations
+      .iter()
+      .filter(|sr| sr.passenger_id() == Some(id))
+      .collect();
+    bookings.sort_by_key(|sr| {
+      let trip = self.show_trip(sr.trip_id());
+      (trip.map(|t| t.departure), sr.trip_id().0)
+    });
+    bookings
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      create_passenger::CreatePassenger,
+      fixtures::{database, passenger},
+      reserve_seat::{self, ReserveSeat},
+      time::Timestamp,
+      trip::{self, Trip},
+    },
+  };
+
+  #[test]
+  fn bookings_by_departure() {
+    let mut database = database();
+    database.trips[0].departure = Timestamp(100);
+    database.trips.push(Trip {
+      id: trip::Id(1),
+      departure: Timestamp(50),
+      ..database.trips[0]
+    });
+    let _ = database.create_passenger(passenger());
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      passenger_id: Some(passenger::Id(0)),
+      ..Default::default()
+    };
+    for trip_id in [trip::Id(0), trip::Id(1)] {
+      let _ = database.reserve_seat(reserve_seat::Form {
+        trip_id,
+        ..form.clone()
+      });
+    }
+
+    assert_eq!(
+      database
+        .list_passenger_bookings(passenger::Id(0))
+        .iter()
+        .map(|sr| sr.trip_id())
+        .collect::<Vec<_>>(),
+      [trip::Id(1), trip::Id(0)]
+    );
+    assert!(database
+      .list_passenger_bookings(passenger::Id(1))
+      .is_empty());
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        passenger_id: Some(passenger::Id(1)),
+        ..form
+      }),
+      Err(reserve_seat::Error::PassengerNotFound)
+    );
+  }
+}
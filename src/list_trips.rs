@@ -0,0 +1,101 @@
+use crate::{route, show_route::ShowRoute, trip::Trip, Database};
+
+#[allow(dead_code)]
+pub trait ListTrips {
+  /// All trips in chronological order of departure.
+  fn list_trips(&self) -> Vec<&Trip>;
+
+  /// Trips grouped by the corridor of their route, corridors in id order.
+  fn list_trips_by_corridor(&self) -> Vec<(route::Id, Vec<&Trip>)>;
+}
+
+impl ListTrips for Database {
+  fn list_trips(&self) -> Vec<&Trip> {
+    let mut trips: Vec<&Trip> = self.trips.iter().collect();
+    trips.sort_by_key(|t| (t.departure, t.id.0));
+    trips
+  }
+
+  fn list_trips_by_corridor(&self) -> Vec<(route::Id, Vec<&Trip>)> {
+    let mut groups: Vec<(route::Id, Vec<&Trip>)> = vec![];
+    for trip in self.list_trips() {
+      let corridor = self.corridor_of(trip.route_id);
+      match groups.iter_mut().find(|(id, _)| *id == corridor) {
+        Some((_, trips)) => trips.push(trip),
+        None => groups.push((corridor, vec![trip])),
+      }
+    }
+    groups.sort_by_key(|(id, _)| id.0);
+    groups
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{route::Route, time::Timestamp, trip},
+  };
+
+  #[test]
+  fn sorted_by_departure() {
+    let database: Database = Database {
+      trips: vec![
+        Trip {
+          id: trip::Id(0),
+          departure: Timestamp(200),
+          ..Default::default()
+        },
+        Trip {
+          id: trip::Id(1),
+          departure: Timestamp(100),
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    };
+
+    let ids: Vec<trip::Id> = database.list_trips().iter().map(|t| t.id).collect();
+
+    assert_eq!(ids, vec![trip::Id(1), trip::Id(0)]);
+  }
+
+  #[test]
+  fn grouped_by_corridor() {
+    let route = |id: u64, base_id: Option<u64>| Route {
+      id: route::Id(id),
+      base_id: base_id.map(route::Id),
+      ..Default::default()
+    };
+    let trip = |id: u64, route_id: u64| Trip {
+      id: trip::Id(id),
+      route_id: route::Id(route_id),
+      departure: Timestamp(100 - i64::try_from(id).unwrap_or_default()),
+      ..Default::default()
+    };
+    let database: Database = Database {
+      routes: vec![
+        route(0, None),
+        route(1, Some(0)),
+        route(2, Some(1)),
+        route(3, None),
+      ],
+      trips: vec![trip(0, 2), trip(1, 3), trip(2, 0), trip(3, 1)],
+      ..Default::default()
+    };
+
+    let groups: Vec<(route::Id, Vec<trip::Id>)> = database
+      .list_trips_by_corridor()
+      .into_iter()
+      .map(|(corridor, trips)| (corridor, trips.iter().map(|t| t.id).collect()))
+      .collect();
+
+    assert_eq!(
+      groups,
+      vec![
+        (route::Id(0), vec![trip::Id(3), trip::Id(2), trip::Id(0)]),
+        (route::Id(3), vec![trip::Id(1)]),
+      ]
+    );
+  }
+}
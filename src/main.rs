@@ -6,24 +6,77 @@
 #![warn(clippy::perf)]
 #![warn(clippy::unwrap_used)]
 
+mod assign_platform;
+mod block_seat;
+mod bool_matrix;
 mod bus;
+mod bus_assignment;
 mod bus_model;
+mod cancel_order;
+mod cancel_ticket;
+mod cancellation;
 mod create_bus;
 mod create_bus_model;
+mod create_customer;
+mod create_order;
+mod create_passenger;
 mod create_route;
+mod create_schedule;
 mod create_stop;
 mod create_trip;
+mod customer;
+mod derive_route;
+mod discount;
+mod fare;
+mod fare_type;
+mod find_stops_near;
+#[cfg(test)]
+mod fixtures;
+mod geo;
+mod lift_seat_block;
+mod list_orders;
+mod list_passenger_bookings;
+mod list_trips;
+mod materialize_schedule;
+mod modify_order;
+mod money;
+mod order;
+mod passenger;
+mod payment;
+mod payment_provider;
+mod place_order;
+mod process_payment;
+mod promo_code;
+mod quote_fare;
+mod rebook_ticket;
+mod refund;
 mod reserve_seat;
+mod reserve_seat_of_class;
 mod route;
+mod schedule;
+mod search_stops;
+mod seat_block;
+mod seat_layout;
+mod seat_reservation;
 mod show_bus;
 mod show_bus_model;
+mod show_class_availability;
+mod show_passenger;
+mod show_payment;
+mod show_route;
+mod show_route_legs;
+mod show_seat_map;
+mod show_station;
 mod show_stop;
+mod show_ticket;
+mod show_timetable;
+mod show_trip;
+mod show_trip_statistics;
 mod stop;
+mod swap_bus;
+mod time;
 mod trip;
-mod show_trip;
-mod show_route;
-mod seat_reservation;
-mod bool_matrix;
+mod varint;
 
 use seat_reservation::SeatReservation;
 
@@ -51,7 +104,23 @@ struct Database {
   route_stops: Vec<route::Stop>,
   routes: Vec<Route>,
   trips: Vec<Trip>,
-  seat_reservations: Vec<SeatReservation>
+  seat_reservations: Vec<SeatReservation>,
+  schedules: Vec<schedule::Schedule>,
+  seat_blocks: Vec<seat_block::SeatBlock>,
+  /// Handed out by `block_seat` and never reused, even once a block is
+  /// lifted.
+  next_seat_block_id: seat_block::Id,
+  stop_locations: geo::SpatialIndex,
+  fare_types: Vec<fare_type::FareType>,
+  promo_codes: Vec<promo_code::PromoCode>,
+  passengers: Vec<passenger::Passenger>,
+  customers: Vec<customer::Customer>,
+  orders: Vec<order::Order>,
+  payments: Vec<payment::PaymentIntent>,
+  /// Id for the next payment the system starts itself, kept past every id
+  /// used so far.
+  next_payment_id: payment::Id,
+  refunds: Vec<refund::Refund>,
 }
 
 fn main() {
@@ -63,47 +132,72 @@ fn main() {
     routes: vec![],
     trips: vec![],
     seat_reservations: vec![],
+    schedules: vec![],
+    seat_blocks: vec![],
+    next_seat_block_id: seat_block::Id(0),
+    stop_locations: geo::SpatialIndex::default(),
+    fare_types: vec![],
+    promo_codes: vec![],
+    passengers: vec![],
+    customers: vec![],
+    orders: vec![],
+    payments: vec![],
+    next_payment_id: payment::Id(0),
+    refunds: vec![],
   };
-  database.create_bus_model(BusModel {
+  let _ = database.create_bus_model(BusModel {
     id: bus_model::Id(0),
     name: "Modelo 1".to_string(),
     number_of_seats: 1,
+    layout: None,
   });
   database.create_bus(Bus {
     id: bus::Id(0),
     bus_model_id: bus_model::Id(0),
   });
-  database.create_stop(stop::Stop {
+  let _ = database.create_stop(stop::Stop {
     id: stop::Id(0),
     name: "Stop 0".to_string(),
+    ..Default::default()
   });
-  database.create_stop(stop::Stop {
+  let _ = database.create_stop(stop::Stop {
     id: stop::Id(1),
     name: "Stop 1".to_string(),
+    ..Default::default()
   });
-  database.create_route(create_route::Form {
-    route: Route { id: route::Id(0) },
+  let _ = database.create_route(create_route::Form {
+    route: Route {
+      id: route::Id(0),
+      name: "Route 0".to_string(),
+      ..Default::default()
+    },
     stops: vec![
       route::Stop {
         id: StopId(0),
         index: 0,
         stop_id: stop::Id(0),
         route_id: route::Id(0),
+        ..Default::default()
       },
       route::Stop {
         id: StopId(1),
         index: 1,
         stop_id: stop::Id(1),
         route_id: route::Id(0),
+        leg_distance_meters: 1_000,
+        leg_running_time: std::time::Duration::from_mins(5),
+        ..Default::default()
       },
     ],
   });
-  database.create_trip(create_trip::Form {
+  let _ = database.create_trip(create_trip::Form {
     trip: Trip {
       id: trip::Id(0),
       route_id: route::Id(0),
-      bus_id: bus::Id(0),
+      vehicle: trip::Vehicle::Bus(bus::Id(0)),
+      ..Default::default()
     },
+    turnaround: std::time::Duration::ZERO,
   });
   database.show_bus_model(bus_model::Id(0));
   database.show_bus(bus::Id(0));
@@ -115,6 +209,6 @@ fn main() {
     from_stop_index: 0,
     to_stop_index: 0,
     trip_id: trip::Id(1),
+    ..Default::default()
   });
 }
-
@@ -0,0 +1,373 @@
+use {
+  crate::{
+    bus_assignment::{CheckBusAssignment, Conflict},
+    schedule::{self, Schedule},
+    time::{Date, Timestamp},
+    trip::{self, Trip},
+    Database,
+  },
+  std::{error, fmt::Display, time::Duration},
+};
+
+/// Most days a single call materialises, so a mistyped year cannot create
+/// trips for decades.
+pub const MAX_WINDOW_DAYS: i64 = 366;
+
+pub struct Form {
+  pub schedule_id: schedule::Id,
+  pub from: Date,
+  pub to: Date,
+  /// The minimum gap the bus needs between two trips.
+  pub turnaround: Duration,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  ScheduleNotFound,
+  InvalidWindow,
+  /// The window spans more than `MAX_WINDOW_DAYS`.
+  WindowTooLong,
+  /// Every trip id has been handed out.
+  IdsExhausted,
+  BusUnavailable(Conflict),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::ScheduleNotFound => write!(f, "schedule not found"),
+      Self::InvalidWindow => write!(f, "window ends before it starts"),
+      Self::WindowTooLong => write!(f, "window spans more than {MAX_WINDOW_DAYS} days"),
+      Self::IdsExhausted => write!(f, "no trip id is left"),
+      Self::BusUnavailable(conflict) => write!(f, "bus unavailable: {conflict:?}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait MaterializeSchedule {
+  /// Creates the trips the schedule runs within `from..=to` and returns their
+  /// ids. Departures that already have a trip are skipped, so running it
+  /// again over an overlapping window creates nothing twice. Trips get the
+  /// schedule's bus, or only its bus model. If any new trip conflicts with
+  /// another trip of the same bus, nothing is created.
+  fn materialize_schedule(&mut self, form: Form) -> Result<Vec<trip::Id>, Error>;
+}
+
+impl MaterializeSchedule for Database {
+  fn materialize_schedule(&mut self, form: Form) -> Result<Vec<trip::Id>, Error> {
+    if form.to < form.from {
+      return Err(Error::InvalidWindow);
+    }
+    if form.to.days_since_epoch() - form.from.days_since_epoch() >= MAX_WINDOW_DAYS {
+      return Err(Error::WindowTooLong);
+    }
+    let schedule: &Schedule = self
+      .schedules
+      .iter()
+      .find(|s| s.id == form.schedule_id)
+      .ok_or(Error::ScheduleNotFound)?;
+
+    let mut trips: Vec<Trip> = vec![];
+    let mut next_id = self
+      .trips
+      .iter()
+      .try_fold(0, |next: u64, t| {
+        t.id.0.checked_add(1).map(|id| next.max(id))
+      })
+      .ok_or(Error::IdsExhausted)?;
+    let mut date = form.from;
+    while date <= form.to {
+      let departure = Timestamp::from_zoned(date, schedule.departure_time, schedule.time_zone);
+      let exists = self
+        .trips
+        .iter()
+        .any(|t| t.schedule_id == Some(schedule.id) && t.departure == departure);
+      if schedule.runs_on(date) && !exists {
+        trips.push(Trip {
+          id: trip::Id(next_id),
+          route_id: schedule.route_id,
+          vehicle: schedule.vehicle,
+          departure,
+          time_zone: schedule.time_zone,
+          schedule_id: Some(schedule.id),
+          departure_platform: None,
+        });
+        next_id = next_id.checked_add(1).ok_or(Error::IdsExhausted)?;
+      }
+      date = date.next();
+    }
+
+    let ids = trips.iter().map(|t| t.id).collect();
+    let len = self.trips.len();
+    for trip in trips {
+      if let Err(conflict) = self.check_bus_assignment(&trip, form.turnaround) {
+        self.trips.truncate(len);
+        return Err(Error::BusUnavailable(conflict));
+      }
+      self.trips.push(trip);
+    }
+    Ok(ids)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      bus_model,
+      time::{DaylightSaving, Time, TimeZone, Transition, UtcOffset, Weekday},
+      trip::Vehicle,
+    },
+  };
+
+  fn date(day: u8) -> Date {
+    Date {
+      year: 2026,
+      month: 10,
+      day,
+    }
+  }
+
+  fn database() -> Database {
+    Database {
+      trips: vec![Trip {
+        id: trip::Id(4),
+        ..Default::default()
+      }],
+      schedules: vec![Schedule {
+        id: schedule::Id(0),
+        departure_time: Time { hour: 8, minute: 0 },
+        days: vec![
+          Weekday::Monday,
+          Weekday::Tuesday,
+          Weekday::Wednesday,
+          Weekday::Thursday,
+          Weekday::Friday,
+        ],
+        valid_from: date(1),
+        valid_until: date(31),
+        exceptions: vec![date(12)],
+        ..Default::default()
+      }],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn schedule_not_found() {
+    let mut database: Database = Database::default();
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(1),
+      to: date(2),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Err(Error::ScheduleNotFound)
+    );
+  }
+
+  #[test]
+  fn invalid_window() {
+    let mut database: Database = database();
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(2),
+      to: date(1),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Err(Error::InvalidWindow)
+    );
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(1),
+      to: Date {
+        year: 2027,
+        month: 10,
+        day: 2,
+      },
+      turnaround: Duration::ZERO,
+    };
+    assert_eq!(
+      database.materialize_schedule(form),
+      Err(Error::WindowTooLong)
+    );
+    assert_eq!(database.trips.len(), 1);
+  }
+
+  #[test]
+  fn bus_model_schedule_leaves_trips_without_a_bus() {
+    let mut database: Database = database();
+    database.schedules[0].vehicle = Vehicle::BusModel(bus_model::Id(0));
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(9),
+      to: date(13),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Ok(vec![trip::Id(5), trip::Id(6)])
+    );
+    assert!(database.trips[1..]
+      .iter()
+      .all(|t| t.vehicle.bus_id().is_none()));
+  }
+
+  #[test]
+  fn ids_exhausted() {
+    let mut database: Database = database();
+    database.trips[0].id = trip::Id(u64::MAX);
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(9),
+      to: date(13),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Err(Error::IdsExhausted)
+    );
+    assert_eq!(database.trips.len(), 1);
+  }
+
+  #[test]
+  fn weekdays_without_exceptions() {
+    let mut database: Database = database();
+    // Friday 9th to Tuesday 13th, with Monday 12th as a holiday.
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(9),
+      to: date(13),
+      turnaround: Duration::ZERO,
+    };
+
+    let result = database.materialize_schedule(form);
+
+    assert_eq!(result, Ok(vec![trip::Id(5), trip::Id(6)]));
+    assert_eq!(
+      database.trips[1].departure,
+      Timestamp::from_local(date(9), Time { hour: 8, minute: 0 }, UtcOffset::UTC)
+    );
+  }
+
+  #[test]
+  fn keeps_local_time_across_daylight_saving() {
+    let mut database: Database = database();
+    // Daylight saving ends on Sunday 11th.
+    let time_zone = TimeZone {
+      standard: UtcOffset(-240),
+      daylight: Some(DaylightSaving {
+        offset: UtcOffset(-180),
+        starts: Transition {
+          month: 4,
+          week: 1,
+          weekday: Weekday::Sunday,
+          at: Time { hour: 0, minute: 0 },
+        },
+        ends: Transition {
+          month: 10,
+          week: 2,
+          weekday: Weekday::Sunday,
+          at: Time { hour: 0, minute: 0 },
+        },
+      }),
+    };
+    database.schedules[0].time_zone = time_zone;
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(9),
+      to: date(13),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Ok(vec![trip::Id(5), trip::Id(6)])
+    );
+    let eight = Time { hour: 8, minute: 0 };
+    assert_eq!(
+      database.trips[1].departure,
+      Timestamp::from_local(date(9), eight, UtcOffset(-180))
+    );
+    assert_eq!(
+      database.trips[2].departure,
+      Timestamp::from_local(date(13), eight, UtcOffset(-240))
+    );
+    assert_eq!(
+      database.trips[2].departure.to_zoned(time_zone),
+      (date(13), eight)
+    );
+  }
+
+  #[test]
+  fn idempotent() {
+    let mut database: Database = database();
+    let first = Form {
+      schedule_id: schedule::Id(0),
+      from: date(5),
+      to: date(9),
+      turnaround: Duration::ZERO,
+    };
+    let overlapping = Form {
+      schedule_id: schedule::Id(0),
+      from: date(8),
+      to: date(13),
+      turnaround: Duration::ZERO,
+    };
+
+    assert_eq!(
+      database.materialize_schedule(first).map(|ids| ids.len()),
+      Ok(5)
+    );
+    assert_eq!(
+      database.materialize_schedule(overlapping),
+      Ok(vec![trip::Id(10)])
+    );
+    assert_eq!(database.trips.len(), 7);
+  }
+
+  #[test]
+  fn overlaps_existing_trip() {
+    let mut database: Database = database();
+    database.trips.push(Trip {
+      id: trip::Id(7),
+      departure: Timestamp::from_local(
+        date(13),
+        Time {
+          hour: 8,
+          minute: 30,
+        },
+        UtcOffset::UTC,
+      ),
+      ..Default::default()
+    });
+    let form = Form {
+      schedule_id: schedule::Id(0),
+      from: date(9),
+      to: date(13),
+      turnaround: Duration::from_hours(1),
+    };
+
+    assert_eq!(
+      database.materialize_schedule(form),
+      Err(Error::BusUnavailable(Conflict {
+        bus_id: crate::bus::Id(0),
+        earlier: trip::Id(9),
+        later: trip::Id(7),
+        kind: crate::bus_assignment::ConflictKind::Overlap,
+      }))
+    );
+    assert_eq!(database.trips.len(), 2);
+  }
+}
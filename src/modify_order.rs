@@ -0,0 +1,592 @@
+use crate::{
+  cancel_ticket::CancelTicket,
+  money, order,
+  payment_provider::PaymentProvider,
+  place_order::{Error, PlaceOrder},
+  process_payment::{self, ProcessPayment},
+  reserve_seat::{self, ReserveSeat},
+  seat_reservation::{self, SeatReservation},
+  show_payment::ShowPayment,
+  time::Timestamp,
+  Database,
+};
+
+pub struct Form {
+  pub order_id: order::Id,
+  /// The complete new set of seats; seats of the order not listed are
+  /// released.
+  pub items: Vec<reserve_seat::Form>,
+  pub requested_at: Timestamp,
+}
+
+#[allow(dead_code)]
+pub trait ModifyOrder {
+  /// Replaces the seats of an order in one step. The current seats are
+  /// released first so they can be kept or shifted, and are restored if the
+  /// new set cannot be reserved in full or its payment cannot be settled.
+  ///
+  /// When the order's payment was captured the difference to the previous
+  /// total is charged with a new payment or refunded across the captured
+  /// payments, and the new seats are ticketed. A ticket that is not kept on
+  /// the same trip, seat and stops is cancelled as by `cancel_ticket`: its
+  /// trip must not have departed and its cancellation fee is kept.
+  ///
+  /// A payment still being made is brought to the new total: a created one
+  /// gets the new amount, an authorized one is voided so the customer can
+  /// pay again. A charge is handled as by `charge_order`. A refund that
+  /// fails after part of it was returned keeps the new seats, ticketed, and
+  /// reports what was refunded.
+  fn modify_order(&mut self, form: Form, provider: &mut dyn PaymentProvider) -> Result<(), Error>;
+}
+
+impl ModifyOrder for Database {
+  fn modify_order(&mut self, form: Form, provider: &mut dyn PaymentProvider) -> Result<(), Error> {
+    let previous_total = self
+      .orders
+      .iter()
+      .find(|o| o.id == form.order_id)
+      .ok_or(Error::OrderNotFound)?
+      .total;
+    let fees = dropped_ticket_fees(self, &form)?;
+    let (current, others): (Vec<SeatReservation>, Vec<SeatReservation>) = self
+      .seat_reservations
+      .drain(..)
+      .partition(|sr| sr.order_id() == Some(form.order_id));
+    self.seat_reservations = others;
+    let promo_uses: Vec<u32> = self.promo_codes.iter().map(|pc| pc.uses).collect();
+    for reservation in &current {
+      self.release_promo_code(reservation);
+    }
+
+    let result = self
+      .reserve_items(form.order_id, form.items)
+      .and_then(|()| settle(self, form.order_id, previous_total, fees, provider));
+    if result.as_ref().is_err_and(|error| !refunded_in_part(error)) {
+      self
+        .seat_reservations
+        .retain(|sr| sr.order_id() != Some(form.order_id));
+      self.seat_reservations.extend(current);
+      for (promo_code, uses) in self.promo_codes.iter_mut().zip(promo_uses) {
+        promo_code.uses = uses;
+      }
+      if let Some(order) = self.orders.iter_mut().find(|o| o.id == form.order_id) {
+        order.total = previous_total;
+      }
+    }
+    result
+  }
+}
+
+/// Whether money already went back, so the modification must stand.
+const fn refunded_in_part(error: &Error) -> bool {
+  matches!(
+    error,
+    Error::Payment(process_payment::Error::PartiallyRefunded { .. })
+  )
+}
+
+/// Cancellation fees of the order's tickets that no item keeps as they are,
+/// `None` when it has no priced ticket to drop.
+fn dropped_ticket_fees(database: &Database, form: &Form) -> Result<Option<money::Money>, Error> {
+  let mut fees: Option<money::Money> = None;
+  for ticket in database.seat_reservations.iter().filter(|sr| {
+    sr.order_id() == Some(form.order_id)
+      && sr.status() == seat_reservation::Status::Ticketed
+      && !form.items.iter().any(|item| {
+        (
+          item.trip_id,
+          item.seat_index,
+          item.from_stop_index,
+          item.to_stop_index,
+        ) == (sr.trip_id(), sr.seat(), sr.first_stop(), sr.last_stop())
+      })
+  }) {
+    let (_, fee) = database
+      .cancellation_refund(ticket, form.requested_at)
+      .map_err(Error::Cancellation)?;
+    fees = Some(match fees {
+      None => fee,
+      Some(fees) => fees.checked_add(fee).ok_or(Error::MixedCurrencies)?,
+    });
+  }
+  Ok(fees)
+}
+
+/// Brings the order's payment in line with its new total plus the fees of
+/// the dropped tickets.
+fn settle(
+  database: &mut Database,
+  order_id: order::Id,
+  previous_total: Option<money::Money>,
+  fees: Option<money::Money>,
+  provider: &mut dyn PaymentProvider,
+) -> Result<(), Error> {
+  let total = database
+    .orders
+    .iter()
+    .find(|o| o.id == order_id)
+    .and_then(|o| o.total);
+
+  if database.captured_payment(order_id).is_some() {
+    let (Some(total), Some(previous)) = (total, previous_total) else {
+      return Err(Error::Payment(process_payment::Error::OrderNotPriced));
+    };
+    let owed = match fees {
+      Some(fees) => total.checked_add(fees).ok_or(Error::MixedCurrencies)?,
+      None => total,
+    };
+    let refund = if let Some(due) = owed.checked_sub(previous) {
+      if due.amount > 0 {
+        database
+          .charge_order(order_id, due, provider)
+          .map_err(Error::Payment)?;
+      }
+      Ok(())
+    } else {
+      let difference = previous.checked_sub(owed).ok_or(Error::MixedCurrencies)?;
+      database
+        .refund_order(order_id, difference, provider)
+        .map(drop)
+        .map_err(Error::Payment)
+    };
+    if refund.as_ref().is_err_and(|error| !refunded_in_part(error)) {
+      return refund;
+    }
+    for reservation in database
+      .seat_reservations
+      .iter_mut()
+      .filter(|sr| sr.order_id() == Some(order_id))
+    {
+      reservation.set_status(seat_reservation::Status::Ticketed);
+    }
+    return refund;
+  }
+
+  database
+    .follow_total(order_id, provider)
+    .map_err(Error::Payment)
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      cancel_order::{self, CancelOrder},
+      cancel_ticket,
+      cancellation::FeeWindow,
+      discount::Discount,
+      fixtures::{item, order_database, ordered, ticketed},
+      money::{Currency, Money},
+      order::{Order, Status},
+      payment,
+      payment_provider::FakeProvider,
+      place_order,
+      process_payment::{self, ProcessPayment},
+      promo_code::PromoCode,
+      time::Timestamp,
+      trip,
+    },
+    std::time::Duration,
+  };
+
+  fn form(items: Vec<reserve_seat::Form>) -> Form {
+    Form {
+      order_id: order::Id(0),
+      items,
+      requested_at: Timestamp(0),
+    }
+  }
+
+  fn cancel() -> cancel_order::Form {
+    cancel_order::Form {
+      order_id: order::Id(0),
+      requested_at: Timestamp(0),
+    }
+  }
+
+  fn placed() -> Database {
+    let mut database = order_database();
+    let _ = database.place_order(place_order::Form {
+      order: Order::default(),
+      items: vec![item(0, 0), item(1, 0)],
+    });
+    database
+  }
+
+  #[test]
+  fn replaces_seats() {
+    let mut provider = FakeProvider::default();
+    let mut database = placed();
+
+    assert_eq!(
+      database.modify_order(
+        form(vec![item(1, 0), item(1, 1), item(0, 1)]),
+        &mut provider
+      ),
+      Ok(())
+    );
+    assert_eq!(database.seat_reservations.len(), 3);
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(3_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn failed_modification_keeps_seats() {
+    let mut provider = FakeProvider::default();
+    let mut database = placed();
+    let before = database.seat_reservations.clone();
+
+    assert_eq!(
+      database.modify_order(form(vec![item(0, 1), item(0, 1)]), &mut provider),
+      Err(Error::Item {
+        index: 1,
+        error: reserve_seat::Error::SeatAlreadyReserved
+      })
+    );
+    assert_eq!(database.seat_reservations, before);
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(2_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn cancels_as_a_whole() {
+    let mut provider = FakeProvider::default();
+    let mut database = placed();
+
+    assert_eq!(
+      database
+        .cancel_order(cancel(), &mut provider)
+        .map(|cancellation| cancellation.released.len()),
+      Ok(2)
+    );
+    assert!(database.seat_reservations.is_empty());
+    assert_eq!(database.orders[0].status, Status::Cancelled);
+    assert_eq!(
+      database.cancel_order(cancel(), &mut provider),
+      Err(cancel_order::Error::AlreadyCancelled)
+    );
+    assert_eq!(
+      database.modify_order(form(vec![item(0, 0)]), &mut provider),
+      Err(Error::OrderCancelled)
+    );
+  }
+
+  #[test]
+  fn paid_order_is_charged_or_refunded_the_difference() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+
+    assert_eq!(
+      database.modify_order(
+        form(vec![item(0, 0), item(1, 0), item(1, 1)]),
+        &mut provider
+      ),
+      Ok(())
+    );
+    assert_eq!(
+      database.payments[1].amount,
+      Money::new(1_000, Currency::XXX)
+    );
+    assert_eq!(database.payments[1].status, payment::Status::Captured);
+    assert!(database
+      .seat_reservations
+      .iter()
+      .all(|sr| sr.status() == seat_reservation::Status::Ticketed));
+
+    assert_eq!(
+      database.modify_order(form(vec![item(1, 1)]), &mut provider),
+      Ok(())
+    );
+    assert_eq!(
+      database
+        .payments
+        .iter()
+        .map(|p| (p.status, p.refunded.amount))
+        .collect::<Vec<_>>(),
+      [
+        (payment::Status::PartiallyRefunded, 1_000),
+        (payment::Status::Refunded, 1_000)
+      ]
+    );
+    assert_eq!(
+      provider.calls[provider.calls.len() - 2..],
+      [
+        (
+          "refund",
+          "fake-1".to_string(),
+          Money::new(1_000, Currency::XXX)
+        ),
+        (
+          "refund",
+          "fake-0".to_string(),
+          Money::new(1_000, Currency::XXX)
+        )
+      ]
+    );
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn dropped_tickets_follow_the_cancellation_policy() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    database.routes[0].cancellation.windows = vec![FeeWindow {
+      before: Duration::from_hours(24),
+      fee: Discount::Percent(10),
+    }];
+
+    assert_eq!(
+      database.modify_order(
+        Form {
+          requested_at: Timestamp(100_001),
+          ..form(vec![item(0, 0)])
+        },
+        &mut provider
+      ),
+      Err(Error::Cancellation(cancel_ticket::Error::TripDeparted))
+    );
+    assert_eq!(
+      database.modify_order(form(vec![item(0, 0)]), &mut provider),
+      Ok(())
+    );
+    assert_eq!(
+      provider.calls.last(),
+      Some(&(
+        "refund",
+        "fake-0".to_string(),
+        Money::new(900, Currency::XXX)
+      ))
+    );
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn partial_refund_keeps_the_new_seats() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    let _ = database.modify_order(
+      form(vec![item(0, 0), item(1, 0), item(1, 1)]),
+      &mut provider,
+    );
+    provider.succeed_next();
+    provider.fail_next(payment::Failure::Declined);
+
+    assert_eq!(
+      database.modify_order(form(vec![item(1, 1)]), &mut provider),
+      Err(Error::Payment(process_payment::Error::PartiallyRefunded {
+        payment_ids: vec![payment::Id(1)],
+        refunded: Money::new(1_000, Currency::XXX),
+        error: Box::new(process_payment::Error::Provider(payment::Failure::Declined)),
+      }))
+    );
+    assert_eq!(
+      database
+        .seat_reservations
+        .iter()
+        .map(|sr| (sr.trip_id(), sr.seat(), sr.status()))
+        .collect::<Vec<_>>(),
+      [(trip::Id(1), 1, seat_reservation::Status::Ticketed)]
+    );
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn failed_charge_keeps_seats() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    let before = database.seat_reservations.clone();
+    provider.fail_next(payment::Failure::Declined);
+
+    assert_eq!(
+      database.modify_order(
+        form(vec![item(0, 0), item(1, 0), item(1, 1)]),
+        &mut provider
+      ),
+      Err(Error::Payment(process_payment::Error::Provider(
+        payment::Failure::Declined
+      )))
+    );
+    assert_eq!(database.seat_reservations, before);
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(2_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn open_payment_follows_the_total() {
+    let mut provider = FakeProvider::default();
+    let mut database = ordered();
+    let one_seat = || form(vec![item(0, 0)]);
+
+    assert_eq!(database.modify_order(one_seat(), &mut provider), Ok(()));
+    assert_eq!(
+      database.payments[0].amount,
+      Money::new(1_000, Currency::XXX)
+    );
+    assert_eq!(database.payments[0].status, payment::Status::Created);
+
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    assert_eq!(database.modify_order(one_seat(), &mut provider), Ok(()));
+    assert_eq!(database.payments[0].status, payment::Status::Voided);
+    assert_eq!(
+      provider.calls[1],
+      (
+        "void",
+        "fake-0".to_string(),
+        Money::new(1_000, Currency::XXX)
+      )
+    );
+  }
+
+  #[test]
+  fn cancelling_refunds_or_voids_the_payment() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+
+    let cancellation = database.cancel_order(cancel(), &mut provider);
+    assert_eq!(
+      cancellation.map(|c| (c.released.len(), c.refunds.len())),
+      Ok((2, 2))
+    );
+    assert_eq!(database.payments[0].status, payment::Status::Refunded);
+    assert_eq!(database.orders[0].status, Status::Cancelled);
+
+    let mut database = ordered();
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    assert_eq!(
+      database
+        .cancel_order(cancel(), &mut provider)
+        .map(|c| c.refunds.len()),
+      Ok(0)
+    );
+    assert_eq!(database.payments[0].status, payment::Status::Voided);
+    assert!(database.seat_reservations.is_empty());
+  }
+
+  #[test]
+  fn failed_void_keeps_the_earlier_ones() {
+    let mut provider = FakeProvider::default();
+    let mut database = ordered();
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    let mut second = database.payments[0].clone();
+    second.id = payment::Id(1);
+    second.reference = Some("fake-9".to_string());
+    database.payments.push(second);
+
+    provider.succeed_next();
+    provider.fail_next(payment::Failure::Declined);
+    assert_eq!(
+      database.cancel_order(cancel(), &mut provider),
+      Err(cancel_order::Error::Payment(
+        process_payment::Error::Provider(payment::Failure::Declined)
+      ))
+    );
+    assert_eq!(database.payments[0].status, payment::Status::Voided);
+    assert_eq!(database.payments[1].status, payment::Status::Authorized);
+    assert_eq!(database.orders[0].status, Status::Open);
+    assert_eq!(database.seat_reservations.len(), 2);
+
+    assert!(database.cancel_order(cancel(), &mut provider).is_ok());
+    assert_eq!(database.payments[1].status, payment::Status::Voided);
+  }
+
+  #[test]
+  fn departed_tickets_keep_the_order() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+
+    assert_eq!(
+      database.cancel_order(
+        cancel_order::Form {
+          order_id: order::Id(0),
+          requested_at: Timestamp(100_001),
+        },
+        &mut provider
+      ),
+      Err(cancel_order::Error::Ticket(
+        cancel_ticket::Error::TripDeparted
+      ))
+    );
+    assert_eq!(database.seat_reservations.len(), 2);
+    assert_eq!(database.payments[0].status, payment::Status::Captured);
+  }
+
+  #[test]
+  fn refunds_are_checked_before_the_provider_is_called() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    let calls = provider.calls.len();
+    database.routes[0].cancellation.windows = vec![FeeWindow {
+      before: Duration::ZERO,
+      fee: Discount::Fixed(Money::new(100, Currency(*b"USD"))),
+    }];
+
+    assert_eq!(
+      database.cancel_order(cancel(), &mut provider),
+      Err(cancel_order::Error::Ticket(
+        cancel_ticket::Error::CurrencyMismatch
+      ))
+    );
+    database.routes[0].cancellation.windows.clear();
+    database.payments[0].refunded = Money::new(1_500, Currency::XXX);
+    assert_eq!(
+      database.cancel_order(cancel(), &mut provider),
+      Err(cancel_order::Error::Ticket(cancel_ticket::Error::Payment(
+        process_payment::Error::RefundTooLarge
+      )))
+    );
+    assert_eq!(provider.calls.len(), calls);
+    assert_eq!(database.seat_reservations.len(), 2);
+  }
+
+  #[test]
+  fn released_seats_give_back_promo_codes() {
+    let mut provider = FakeProvider::default();
+    let mut database = order_database();
+    database.promo_codes = vec![PromoCode {
+      code: "ONCE".to_string(),
+      discount: Discount::Percent(10),
+      valid_from: Timestamp(0),
+      valid_until: Timestamp(1),
+      max_uses: Some(1),
+      uses: 0,
+      route_ids: vec![],
+    }];
+    let with_code = |trip_id: u64| reserve_seat::Form {
+      promo_code: Some("ONCE".to_string()),
+      ..item(trip_id, 0)
+    };
+    let _ = database.place_order(place_order::Form {
+      order: Order::default(),
+      items: vec![with_code(0)],
+    });
+
+    assert_eq!(
+      database.modify_order(form(vec![with_code(1)]), &mut provider),
+      Ok(())
+    );
+    assert_eq!(database.promo_codes[0].uses, 1);
+    assert_eq!(
+      database
+        .cancel_order(cancel(), &mut provider)
+        .map(|c| c.released.len()),
+      Ok(1)
+    );
+    assert_eq!(database.promo_codes[0].uses, 0);
+  }
+}
@@ -0,0 +1,330 @@
+//! Amounts are integer minor units of a currency, e.g. cents, and every
+//! derived amount goes through an explicit `Rounding`. A chain of
+//! adjustments is carried out on an `Exact` amount and rounded once.
+
+use std::fmt::Display;
+
+/// ISO 4217 code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Currency(pub [u8; 3]);
+
+impl Currency {
+  /// "No currency", used until a route is given one.
+  pub const XXX: Self = Self(*b"XXX");
+}
+
+impl Default for Currency {
+  fn default() -> Self {
+    Self::XXX
+  }
+}
+
+impl Display for Currency {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", String::from_utf8_lossy(&self.0))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Money {
+  pub amount: u64,
+  pub currency: Currency,
+}
+
+impl Money {
+  pub const fn new(amount: u64, currency: Currency) -> Self {
+    Self { amount, currency }
+  }
+
+  pub const fn zero(currency: Currency) -> Self {
+    Self::new(0, currency)
+  }
+
+  /// `None` on overflow or when the currencies differ.
+  pub fn checked_add(self, other: Self) -> Option<Self> {
+    if self.currency != other.currency {
+      return None;
+    }
+    Some(Self::new(
+      self.amount.checked_add(other.amount)?,
+      self.currency,
+    ))
+  }
+
+  pub fn checked_sub(self, other: Self) -> Option<Self> {
+    if self.currency != other.currency {
+      return None;
+    }
+    Some(Self::new(
+      self.amount.checked_sub(other.amount)?,
+      self.currency,
+    ))
+  }
+
+  /// `self × numerator / denominator`, or `None` when it does not fit.
+  #[allow(dead_code)]
+  pub fn scale(self, numerator: u64, denominator: u64, rounding: Rounding) -> Option<Self> {
+    let scaled = rounding.divide(
+      u128::from(self.amount) * u128::from(numerator),
+      u128::from(denominator),
+    )?;
+    Some(Self::new(u64::try_from(scaled).ok()?, self.currency))
+  }
+}
+
+impl Display for Money {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} {}", self.amount, self.currency)
+  }
+}
+
+/// An amount in minor units kept as an exact fraction. The operations give
+/// `None` on overflow, on mixing currencies or below zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exact {
+  numerator: u128,
+  /// Never zero.
+  denominator: u128,
+  pub currency: Currency,
+}
+
+impl From<Money> for Exact {
+  fn from(money: Money) -> Self {
+    Self {
+      numerator: u128::from(money.amount),
+      denominator: 1,
+      currency: money.currency,
+    }
+  }
+}
+
+impl Exact {
+  fn new(numerator: u128, denominator: u128, currency: Currency) -> Self {
+    let divisor = gcd(numerator, denominator).max(1);
+    Self {
+      numerator: numerator / divisor,
+      denominator: denominator / divisor,
+      currency,
+    }
+  }
+
+  /// `self × numerator / denominator`.
+  pub fn scale(self, numerator: u64, denominator: u64) -> Option<Self> {
+    if denominator == 0 {
+      return None;
+    }
+    let by = Self::new(
+      u128::from(numerator),
+      u128::from(denominator),
+      self.currency,
+    );
+    Some(Self::new(
+      self.numerator.checked_mul(by.numerator)?,
+      self.denominator.checked_mul(by.denominator)?,
+      self.currency,
+    ))
+  }
+
+  pub fn checked_add(self, other: Self) -> Option<Self> {
+    let (left, right, denominator) = self.common(other)?;
+    Some(Self::new(
+      left.checked_add(right)?,
+      denominator,
+      self.currency,
+    ))
+  }
+
+  pub fn checked_sub(self, other: Self) -> Option<Self> {
+    let (left, right, denominator) = self.common(other)?;
+    Some(Self::new(
+      left.checked_sub(right)?,
+      denominator,
+      self.currency,
+    ))
+  }
+
+  /// The smaller of two amounts in the same currency.
+  pub fn min(self, other: Self) -> Option<Self> {
+    let (left, right, _) = self.common(other)?;
+    Some(if left <= right { self } else { other })
+  }
+
+  pub fn round(self, rounding: Rounding) -> Option<Money> {
+    let amount = rounding.divide(self.numerator, self.denominator)?;
+    Some(Money::new(u64::try_from(amount).ok()?, self.currency))
+  }
+
+  /// Both numerators over a shared denominator.
+  fn common(self, other: Self) -> Option<(u128, u128, u128)> {
+    if self.currency != other.currency {
+      return None;
+    }
+    Some((
+      self.numerator.checked_mul(other.denominator)?,
+      other.numerator.checked_mul(self.denominator)?,
+      self.denominator.checked_mul(other.denominator)?,
+    ))
+  }
+}
+
+const fn gcd(mut a: u128, mut b: u128) -> u128 {
+  while b != 0 {
+    (a, b) = (b, a % b);
+  }
+  a
+}
+
+/// How a division that does not come out even is settled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Rounding {
+  #[default]
+  HalfUp,
+  /// Banker's rounding: ties go to the even neighbour.
+  HalfEven,
+  Down,
+  Up,
+}
+
+impl Rounding {
+  /// `numerator / denominator`; a zero denominator gives zero. `None` when
+  /// the remainder is too large to compare with half the denominator.
+  pub const fn divide(self, numerator: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+      return Some(0);
+    }
+    let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+    let Some(twice) = remainder.checked_mul(2) else {
+      return None;
+    };
+    let round_up = match self {
+      Self::Down => false,
+      Self::Up => remainder > 0,
+      Self::HalfUp => twice >= denominator,
+      Self::HalfEven => twice > denominator || (twice == denominator && quotient % 2 == 1),
+    };
+    Some(quotient + round_up as u128)
+  }
+}
+
+/// Price conversion set by the operator: one minor unit of the route's
+/// currency is `numerator / denominator` minor units of `currency`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExchangeRate {
+  currency: Currency,
+  numerator: u64,
+  denominator: u64,
+}
+
+impl ExchangeRate {
+  /// `None` for a zero denominator.
+  #[allow(dead_code)]
+  pub const fn new(currency: Currency, numerator: u64, denominator: u64) -> Option<Self> {
+    if denominator == 0 {
+      return None;
+    }
+    Some(Self {
+      currency,
+      numerator,
+      denominator,
+    })
+  }
+
+  pub const fn currency(&self) -> Currency {
+    self.currency
+  }
+
+  /// `amount` in the route's currency converted to `self.currency()`.
+  pub fn convert(&self, amount: Exact) -> Option<Exact> {
+    let converted = amount.scale(self.numerator, self.denominator)?;
+    Some(Exact {
+      currency: self.currency,
+      ..converted
+    })
+  }
+}
+
+/// A tax charged on top of the discounted fare, in basis points (1900 is
+/// 19 %).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaxRule {
+  pub name: String,
+  pub basis_points: u32,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rounding_modes() {
+    let divide = |rounding: Rounding| [5, 15, 16, 14].map(|n| rounding.divide(n, 10));
+
+    assert_eq!(divide(Rounding::HalfUp), [1, 2, 2, 1].map(Some));
+    assert_eq!(divide(Rounding::HalfEven), [0, 2, 2, 1].map(Some));
+    assert_eq!(divide(Rounding::Down), [0, 1, 1, 1].map(Some));
+    assert_eq!(divide(Rounding::Up), [1, 2, 2, 2].map(Some));
+  }
+
+  #[test]
+  fn overflow_is_none() {
+    let clp = Currency(*b"CLP");
+
+    assert_eq!(Rounding::HalfUp.divide(u128::MAX - 1, u128::MAX), None);
+    assert_eq!(Rounding::Down.divide(u128::MAX, 0), Some(0));
+    assert_eq!(
+      Money::new(u64::MAX, clp).scale(3, 2, Rounding::HalfUp),
+      None
+    );
+    assert_eq!(
+      Money::new(u64::MAX, clp).scale(1, 2, Rounding::Down),
+      Some(Money::new(u64::MAX / 2, clp))
+    );
+  }
+
+  #[test]
+  fn currencies_do_not_mix() {
+    let (clp, ars) = (Currency(*b"CLP"), Currency(*b"ARS"));
+
+    assert_eq!(
+      Money::new(5, clp).checked_add(Money::new(7, clp)),
+      Some(Money::new(12, clp))
+    );
+    assert_eq!(Money::new(5, clp).checked_add(Money::new(7, ars)), None);
+    assert_eq!(Money::new(5, clp).checked_sub(Money::new(7, clp)), None);
+  }
+
+  #[test]
+  fn exchange_rate_needs_a_denominator() {
+    let ars = Currency(*b"ARS");
+
+    assert_eq!(ExchangeRate::new(ars, 1, 0), None);
+    assert_eq!(
+      ExchangeRate::new(ars, 3, 2)
+        .and_then(|rate| rate.convert(Money::new(101, Currency::XXX).into()))
+        .and_then(|converted| converted.round(Rounding::HalfUp)),
+      Some(Money::new(152, ars))
+    );
+  }
+
+  #[test]
+  fn exact_amounts_round_once() {
+    let clp = Currency(*b"CLP");
+    let price = Exact::from(Money::new(1_005, clp));
+    let halved_twice = price
+      .scale(1, 2)
+      .and_then(|half| half.scale(1, 2))
+      .and_then(|quarter| quarter.checked_add(Exact::from(Money::new(1, clp))));
+
+    assert_eq!(
+      halved_twice.and_then(|amount| amount.round(Rounding::Down)),
+      Some(Money::new(252, clp))
+    );
+    assert_eq!(
+      halved_twice.and_then(|amount| amount.round(Rounding::Up)),
+      Some(Money::new(253, clp))
+    );
+    assert_eq!(price.checked_sub(Exact::from(Money::new(1_006, clp))), None);
+    assert_eq!(price.min(Exact::from(Money::new(3, Currency::XXX))), None);
+  }
+}
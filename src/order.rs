@@ -0,0 +1,22 @@
+use crate::{customer, money::Money, time::Timestamp};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// A purchase by a customer. Its reservations point back to it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Order {
+  pub id: Id,
+  pub customer_id: customer::Id,
+  pub placed_at: Timestamp,
+  pub status: Status,
+  /// Sum of the quotes of its reservations, when they are priced.
+  pub total: Option<Money>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Status {
+  #[default]
+  Open,
+  Cancelled,
+}
@@ -0,0 +1,149 @@
+use crate::seat_layout::PassengerAttribute;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// The person travelling, as opposed to whoever pays.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Passenger {
+  pub id: Id,
+  pub name: String,
+  pub document: Document,
+  pub nationality: Country,
+  pub contact: Contact,
+  /// Needs that entitle the passenger to restricted seats.
+  pub attributes: Vec<PassengerAttribute>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+  pub kind: DocumentKind,
+  pub number: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum DocumentKind {
+  /// Chilean national id, e.g. "12.345.678-5".
+  #[default]
+  Rut,
+  Passport,
+  /// Any other national id card.
+  NationalId,
+}
+
+/// ISO 3166-1 alpha-2 code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Country(pub [u8; 2]);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Contact {
+  pub email: Option<String>,
+  pub phone: Option<String>,
+}
+
+impl Document {
+  /// The number without separators, upper case. Documents compare by this.
+  pub fn normalized_number(&self) -> String {
+    self
+      .number
+      .chars()
+      .filter(|c| !matches!(c, '.' | '-' | ' '))
+      .map(|c| c.to_ascii_uppercase())
+      .collect()
+  }
+
+  pub fn is_valid(&self) -> bool {
+    let number = self.normalized_number();
+    let alphanumeric = number.chars().all(|c| c.is_ascii_alphanumeric());
+    match self.kind {
+      DocumentKind::Rut => is_valid_rut(&number),
+      DocumentKind::Passport => alphanumeric && (6..=9).contains(&number.len()),
+      DocumentKind::NationalId => alphanumeric && (4..=20).contains(&number.len()),
+    }
+  }
+
+  pub fn same_as(&self, other: &Self) -> bool {
+    self.kind == other.kind && self.normalized_number() == other.normalized_number()
+  }
+}
+
+/// Up to eight digits followed by the modulo 11 check digit, `K` for 10.
+fn is_valid_rut(number: &str) -> bool {
+  let Some((body, check)) = number.split_at_checked(number.len().saturating_sub(1)) else {
+    return false;
+  };
+  if body.is_empty() || body.len() > 8 || !body.chars().all(|c| c.is_ascii_digit()) {
+    return false;
+  }
+  let sum: u32 = body
+    .chars()
+    .rev()
+    .filter_map(|c| c.to_digit(10))
+    .zip([2, 3, 4, 5, 6, 7].into_iter().cycle())
+    .map(|(digit, weight)| digit * weight)
+    .sum();
+  let expected = match 11 - sum % 11 {
+    11 => '0',
+    10 => 'K',
+    digit => char::from_digit(digit, 10).unwrap_or('?'),
+  };
+  check.chars().eq([expected])
+}
+
+impl Country {
+  pub const fn is_valid(self) -> bool {
+    self.0[0].is_ascii_uppercase() && self.0[1].is_ascii_uppercase()
+  }
+}
+
+impl Contact {
+  /// A single `@` with a local part and a dotted domain.
+  pub fn is_valid_email(email: &str) -> bool {
+    email.split_once('@').is_some_and(|(local, domain)| {
+      !local.is_empty()
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+    })
+  }
+
+  /// 7 to 15 digits, optionally after a leading `+`; spaces are ignored.
+  pub fn is_valid_phone(phone: &str) -> bool {
+    let digits: String = phone
+      .strip_prefix('+')
+      .unwrap_or(phone)
+      .chars()
+      .filter(|c| *c != ' ')
+      .collect();
+    digits.chars().all(|c| c.is_ascii_digit()) && (7..=15).contains(&digits.len())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rut_check_digit() {
+    let rut = |number: &str| Document {
+      kind: DocumentKind::Rut,
+      number: number.to_string(),
+    };
+
+    assert!(rut("12.345.678-5").is_valid());
+    assert!(rut("10.000.013-k").is_valid());
+    assert!(!rut("12.345.678-6").is_valid());
+    assert!(!rut("5").is_valid());
+    assert!(rut("12345678-5").same_as(&rut("12.345.678-5")));
+  }
+
+  #[test]
+  fn contact_formats() {
+    assert!(Contact::is_valid_email("ana@example.cl"));
+    assert!(!Contact::is_valid_email("ana@example"));
+    assert!(!Contact::is_valid_email("@example.cl"));
+    assert!(Contact::is_valid_phone("+56 9 1234 5678"));
+    assert!(!Contact::is_valid_phone("12-34"));
+  }
+}
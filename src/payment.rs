@@ -0,0 +1,53 @@
+use crate::{money::Money, order};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// Payment of an order's total, moved through its lifecycle by
+/// `ProcessPayment`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PaymentIntent {
+  pub id: Id,
+  pub order_id: order::Id,
+  pub amount: Money,
+  pub status: Status,
+  /// The provider's id for the payment, known once authorized.
+  pub reference: Option<String>,
+  /// Returned to the customer so far.
+  pub refunded: Money,
+}
+
+impl PaymentIntent {
+  /// Whether the provider has taken the funds, whatever was refunded since.
+  pub const fn is_captured(&self) -> bool {
+    matches!(
+      self.status,
+      Status::Captured | Status::PartiallyRefunded | Status::Refunded
+    )
+  }
+}
+
+/// `Created → Authorized → Captured → PartiallyRefunded → Refunded`, or
+/// `Failed` or `Voided` from the first two. An authorization or capture that times out
+/// leaves the status as it was, since the provider may or may not have gone
+/// through with it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Status {
+  #[default]
+  Created,
+  Authorized,
+  Captured,
+  /// Some, but not all, of the captured amount has been returned.
+  PartiallyRefunded,
+  Refunded,
+  Failed(Failure),
+  /// Dropped before capture, with any authorized funds released.
+  Voided,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Failure {
+  Declined,
+  TimedOut,
+}
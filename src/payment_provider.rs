@@ -0,0 +1,72 @@
+use {
+  crate::{money::Money, payment::Failure},
+  std::collections::VecDeque,
+};
+
+/// A card processor or wallet. A `TimedOut` failure leaves the outcome
+/// unknown: the call may have gone through.
+pub trait PaymentProvider {
+  /// Reserves the funds, returning the provider's reference for them.
+  fn authorize(&mut self, amount: Money) -> Result<String, Failure>;
+
+  fn capture(&mut self, reference: &str, amount: Money) -> Result<(), Failure>;
+
+  fn refund(&mut self, reference: &str, amount: Money) -> Result<(), Failure>;
+
+  /// Releases authorized funds that will not be captured.
+  fn void(&mut self, reference: &str, amount: Money) -> Result<(), Failure>;
+}
+
+/// In-process provider for tests. Each call consumes the next scripted
+/// outcome and succeeds once the script is empty.
+#[derive(Debug, Default)]
+pub struct FakeProvider {
+  pub script: VecDeque<Result<(), Failure>>,
+  /// Successful calls as (operation, reference, amount).
+  pub calls: Vec<(&'static str, String, Money)>,
+  authorized: u64,
+}
+
+impl FakeProvider {
+  #[allow(dead_code)]
+  pub fn fail_next(&mut self, failure: Failure) {
+    self.script.push_back(Err(failure));
+  }
+
+  #[allow(dead_code)]
+  pub fn succeed_next(&mut self) {
+    self.script.push_back(Ok(()));
+  }
+
+  fn call(
+    &mut self,
+    operation: &'static str,
+    reference: String,
+    amount: Money,
+  ) -> Result<(), Failure> {
+    self.script.pop_front().unwrap_or(Ok(()))?;
+    self.calls.push((operation, reference, amount));
+    Ok(())
+  }
+}
+
+impl PaymentProvider for FakeProvider {
+  fn authorize(&mut self, amount: Money) -> Result<String, Failure> {
+    let reference = format!("fake-{}", self.authorized);
+    self.call("authorize", reference.clone(), amount)?;
+    self.authorized += 1;
+    Ok(reference)
+  }
+
+  fn capture(&mut self, reference: &str, amount: Money) -> Result<(), Failure> {
+    self.call("capture", reference.to_string(), amount)
+  }
+
+  fn refund(&mut self, reference: &str, amount: Money) -> Result<(), Failure> {
+    self.call("refund", reference.to_string(), amount)
+  }
+
+  fn void(&mut self, reference: &str, amount: Money) -> Result<(), Failure> {
+    self.call("void", reference.to_string(), amount)
+  }
+}
@@ -0,0 +1,234 @@
+use {
+  crate::{
+    cancel_ticket,
+    create_order::{self, CreateOrder},
+    money::Money,
+    order::{self, Order},
+    process_payment,
+    reserve_seat::{self, ReserveSeat},
+    Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub order: Order,
+  /// Seats on any number of trips. Their `order_id` is set from the order.
+  pub items: Vec<reserve_seat::Form>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  Empty,
+  OrderNotFound,
+  OrderCancelled,
+  CreateOrder(create_order::Error),
+  Item {
+    index: usize,
+    error: reserve_seat::Error,
+  },
+  MixedCurrencies,
+  /// A ticket the modification drops cannot be cancelled.
+  Cancellation(cancel_ticket::Error),
+  /// The order's payment could not be brought to its new total.
+  Payment(process_payment::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Empty => write!(f, "an order needs at least one seat"),
+      Self::OrderNotFound => write!(f, "order not found"),
+      Self::OrderCancelled => write!(f, "order is cancelled"),
+      Self::CreateOrder(error) => write!(f, "{error}"),
+      Self::Item { index, error } => write!(f, "seat {index} cannot be reserved: {error}"),
+      Self::MixedCurrencies => write!(f, "seats are priced in different currencies"),
+      Self::Cancellation(error) => write!(f, "ticket cannot be dropped: {error}"),
+      Self::Payment(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+pub trait PlaceOrder {
+  /// Creates the order and reserves all of its seats, or nothing at all.
+  #[allow(dead_code)]
+  fn place_order(&mut self, form: Form) -> Result<(), Error>;
+
+  /// Reserves the items into an existing order, paid or not, and sets its
+  /// total. On any failure the reservations and promo code uses made so far
+  /// are undone.
+  fn reserve_items(
+    &mut self,
+    order_id: order::Id,
+    items: Vec<reserve_seat::Form>,
+  ) -> Result<(), Error>;
+
+  /// Sum of the quotes of the order's reservations, `None` when none is
+  /// priced.
+  fn order_total(&self, order_id: order::Id) -> Result<Option<Money>, Error>;
+}
+
+impl PlaceOrder for Database {
+  fn place_order(&mut self, form: Form) -> Result<(), Error> {
+    let order_id = form.order.id;
+    self.create_order(form.order).map_err(Error::CreateOrder)?;
+
+    let result = self.reserve_items(order_id, form.items);
+    if result.is_err() {
+      self.orders.retain(|o| o.id != order_id);
+    }
+    result
+  }
+
+  fn reserve_items(
+    &mut self,
+    order_id: order::Id,
+    items: Vec<reserve_seat::Form>,
+  ) -> Result<(), Error> {
+    if items.is_empty() {
+      return Err(Error::Empty);
+    }
+    let order = self
+      .orders
+      .iter()
+      .find(|o| o.id == order_id)
+      .ok_or(Error::OrderNotFound)?;
+    if order.status == order::Status::Cancelled {
+      return Err(Error::OrderCancelled);
+    }
+
+    let reserved = self.seat_reservations.len();
+    let promo_uses: Vec<u32> = self.promo_codes.iter().map(|pc| pc.uses).collect();
+    let result = items
+      .into_iter()
+      .enumerate()
+      .try_for_each(|(index, item)| {
+        self
+          .reserve_seat(reserve_seat::Form {
+            order_id: None,
+            ..item
+          })
+          .map_err(|error| Error::Item { index, error })?;
+        if let Some(reservation) = self.seat_reservations.last_mut() {
+          reservation.set_order_id(Some(order_id));
+        }
+        Ok(())
+      })
+      .and_then(|()| self.order_total(order_id));
+
+    match result {
+      Ok(total) => {
+        if let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) {
+          order.total = total;
+        }
+        Ok(())
+      }
+      Err(error) => {
+        self.seat_reservations.truncate(reserved);
+        for (promo_code, uses) in self.promo_codes.iter_mut().zip(promo_uses) {
+          promo_code.uses = uses;
+        }
+        Err(error)
+      }
+    }
+  }
+
+  fn order_total(&self, order_id: order::Id) -> Result<Option<Money>, Error> {
+    let mut total: Option<Money> = None;
+    for sr in self
+      .seat_reservations
+      .iter()
+      .filter(|sr| sr.order_id() == Some(order_id))
+    {
+      let Some(quote) = sr.quote() else { continue };
+      total = Some(match total {
+        None => quote.total,
+        Some(total) => total
+          .checked_add(quote.total)
+          .ok_or(Error::MixedCurrencies)?,
+      });
+    }
+    Ok(total)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      discount::Discount,
+      fixtures::{item, order_database},
+      money::Currency,
+      promo_code::PromoCode,
+      time::Timestamp,
+    },
+  };
+
+  #[test]
+  fn return_journey() {
+    let mut database = order_database();
+    let form = Form {
+      order: Order {
+        id: order::Id(0),
+        placed_at: Timestamp(0),
+        ..Default::default()
+      },
+      items: vec![item(0, 0), item(1, 0)],
+    };
+
+    assert_eq!(database.place_order(form), Ok(()));
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(2_000, Currency::XXX))
+    );
+    assert!(database
+      .seat_reservations
+      .iter()
+      .all(|sr| sr.order_id() == Some(order::Id(0))));
+  }
+
+  #[test]
+  fn all_or_nothing() {
+    let mut database = order_database();
+    database.promo_codes = vec![PromoCode {
+      code: "ONCE".to_string(),
+      discount: Discount::Percent(10),
+      valid_from: Timestamp(0),
+      valid_until: Timestamp(1),
+      max_uses: Some(1),
+      uses: 0,
+      route_ids: vec![],
+    }];
+    let _ = database.reserve_seat(item(1, 0));
+    let form = Form {
+      order: Order::default(),
+      items: vec![
+        reserve_seat::Form {
+          promo_code: Some("ONCE".to_string()),
+          ..item(0, 0)
+        },
+        item(1, 0),
+      ],
+    };
+
+    assert_eq!(
+      database.place_order(form),
+      Err(Error::Item {
+        index: 1,
+        error: reserve_seat::Error::SeatAlreadyReserved
+      })
+    );
+    assert!(database.orders.is_empty());
+    assert_eq!(database.seat_reservations.len(), 1);
+    assert_eq!(database.promo_codes[0].uses, 0);
+    assert_eq!(
+      database.place_order(Form {
+        order: Order::default(),
+        items: vec![],
+      }),
+      Err(Error::Empty)
+    );
+  }
+}
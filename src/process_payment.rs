@@ -0,0 +1,653 @@
+use {
+  crate::{
+    money::Money,
+    order,
+    payment::{self, Failure, PaymentIntent, Status},
+    payment_provider::PaymentProvider,
+    seat_reservation,
+    show_payment::ShowPayment,
+    Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub id: payment::Id,
+  pub order_id: order::Id,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  PaymentNotFound,
+  DuplicateId,
+  /// Every payment id has been handed out.
+  IdsExhausted,
+  OrderNotFound,
+  OrderCancelled,
+  OrderNotPriced,
+  /// The order already has a payment that has not failed or been voided.
+  AlreadyPaid(payment::Id),
+  /// The payment's status does not allow the operation.
+  InvalidStatus(Status),
+  Provider(Failure),
+  RefundTooLarge,
+  /// A refund across several payments failed after the ones it lists had
+  /// returned `refunded`.
+  PartiallyRefunded {
+    payment_ids: Vec<payment::Id>,
+    refunded: Money,
+    error: Box<Self>,
+  },
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::PaymentNotFound => write!(f, "payment not found"),
+      Self::DuplicateId => write!(f, "a payment with this id exists"),
+      Self::IdsExhausted => write!(f, "no payment id is left"),
+      Self::OrderNotFound => write!(f, "order not found"),
+      Self::OrderCancelled => write!(f, "order is cancelled"),
+      Self::OrderNotPriced => write!(f, "order has no price"),
+      Self::AlreadyPaid(id) => write!(f, "order is already paid by payment {}", id.0),
+      Self::InvalidStatus(status) => write!(f, "not possible for a payment that is {status:?}"),
+      Self::Provider(failure) => write!(f, "payment provider failed: {failure:?}"),
+      Self::RefundTooLarge => write!(f, "refund exceeds the captured amount"),
+      Self::PartiallyRefunded {
+        refunded, error, ..
+      } => write!(f, "{error} after {refunded} was refunded"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[allow(dead_code)]
+pub trait ProcessPayment {
+  /// Starts paying the order's total. A failed payment may be retried with a
+  /// new one; the seats stay held meanwhile.
+  fn create_payment(&mut self, form: Form) -> Result<(), Error>;
+
+  /// After a timeout the payment stays created, so the order cannot get a
+  /// second payment while the provider may have authorized this one.
+  fn authorize_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error>;
+
+  /// Takes the authorized funds and turns the order's held seats into
+  /// tickets. After a timeout the payment stays authorized so the capture
+  /// can be retried.
+  fn capture_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error>;
+
+  /// Returns part or all of a captured payment.
+  fn refund_payment(
+    &mut self,
+    id: payment::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error>;
+
+  /// Returns `amount` through the order's captured payments in its currency,
+  /// newest first, and gives the payments it went through. Fails before any
+  /// refund when they hold less than `amount`. A refund that fails after
+  /// others were made stops it with `PartiallyRefunded`, which the caller
+  /// must keep, since that money is gone.
+  fn refund_order(
+    &mut self,
+    order_id: order::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Vec<payment::Id>, Error>;
+
+  /// Brings a payment still being made in line with the order's total: a
+  /// created one gets the new amount, an authorized one is voided so the
+  /// customer can pay again, as is a created one once the order has no
+  /// total.
+  fn follow_total(
+    &mut self,
+    order_id: order::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error>;
+
+  /// Drops a payment that was not captured, releasing authorized funds.
+  /// After a timeout the payment stays authorized so the void can be
+  /// retried.
+  fn void_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error>;
+
+  /// Takes `amount` from the customer of an open order whose payment was
+  /// already captured, with a new payment that also tickets the order's held
+  /// seats. The payment gets the next id, which must still be free.
+  /// A capture that times out is voided so no funds stay held for a change
+  /// being rolled back, and stays authorized for reconciliation only when
+  /// the void fails too. An authorization that times out stays created,
+  /// keeping the order from another payment while it may have gone through.
+  fn charge_order(
+    &mut self,
+    order_id: order::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<payment::Id, Error>;
+}
+
+impl ProcessPayment for Database {
+  fn create_payment(&mut self, form: Form) -> Result<(), Error> {
+    if self.payments.iter().any(|p| p.id == form.id) {
+      return Err(Error::DuplicateId);
+    }
+    let order = self
+      .orders
+      .iter()
+      .find(|o| o.id == form.order_id)
+      .ok_or(Error::OrderNotFound)?;
+    if order.status == order::Status::Cancelled {
+      return Err(Error::OrderCancelled);
+    }
+    let amount = order.total.ok_or(Error::OrderNotPriced)?;
+    if let Some(paid) = self.payments.iter().find(|p| {
+      p.order_id == form.order_id && !matches!(p.status, Status::Failed(_) | Status::Voided)
+    }) {
+      return Err(Error::AlreadyPaid(paid.id));
+    }
+
+    self.next_payment_id = payment::Id(self.next_payment_id.0.max(form.id.0.saturating_add(1)));
+    self.payments.push(PaymentIntent {
+      id: form.id,
+      order_id: form.order_id,
+      amount,
+      status: Status::Created,
+      reference: None,
+      refunded: Money::zero(amount.currency),
+    });
+    Ok(())
+  }
+
+  fn authorize_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error> {
+    let payment = find(&mut self.payments, id)?;
+    if payment.status != Status::Created {
+      return Err(Error::InvalidStatus(payment.status));
+    }
+
+    match provider.authorize(payment.amount) {
+      Ok(reference) => {
+        payment.reference = Some(reference);
+        payment.status = Status::Authorized;
+        Ok(())
+      }
+      Err(failure) => {
+        if failure != Failure::TimedOut {
+          payment.status = Status::Failed(failure);
+        }
+        Err(Error::Provider(failure))
+      }
+    }
+  }
+
+  fn capture_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error> {
+    let order_id = find(&mut self.payments, id)?.order_id;
+    if !self
+      .orders
+      .iter()
+      .any(|o| o.id == order_id && o.status == order::Status::Open)
+    {
+      return Err(Error::OrderCancelled);
+    }
+    let payment = find(&mut self.payments, id)?;
+    let (Status::Authorized, Some(reference)) = (payment.status, &payment.reference) else {
+      return Err(Error::InvalidStatus(payment.status));
+    };
+
+    if let Err(failure) = provider.capture(reference, payment.amount) {
+      if failure != Failure::TimedOut {
+        payment.status = Status::Failed(failure);
+      }
+      return Err(Error::Provider(failure));
+    }
+    payment.status = Status::Captured;
+    for reservation in self
+      .seat_reservations
+      .iter_mut()
+      .filter(|sr| sr.order_id() == Some(order_id))
+    {
+      reservation.set_status(seat_reservation::Status::Ticketed);
+    }
+    Ok(())
+  }
+
+  fn refund_payment(
+    &mut self,
+    id: payment::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error> {
+    let payment = find(&mut self.payments, id)?;
+    let (Status::Captured | Status::PartiallyRefunded, Some(reference)) =
+      (payment.status, &payment.reference)
+    else {
+      return Err(Error::InvalidStatus(payment.status));
+    };
+    let refunded = payment
+      .refunded
+      .checked_add(amount)
+      .filter(|total| total.amount <= payment.amount.amount)
+      .ok_or(Error::RefundTooLarge)?;
+
+    provider
+      .refund(reference, amount)
+      .map_err(Error::Provider)?;
+    payment.refunded = refunded;
+    payment.status = if refunded == payment.amount {
+      Status::Refunded
+    } else {
+      Status::PartiallyRefunded
+    };
+    Ok(())
+  }
+
+  fn refund_order(
+    &mut self,
+    order_id: order::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Vec<payment::Id>, Error> {
+    self
+      .refundable(order_id)
+      .and_then(|refundable| refundable.checked_sub(amount))
+      .ok_or(Error::RefundTooLarge)?;
+    let mut left = amount.amount;
+    let shares: Vec<(payment::Id, Money)> = self
+      .payments
+      .iter()
+      .rev()
+      .filter(|p| p.order_id == order_id && p.is_captured() && p.amount.currency == amount.currency)
+      .filter_map(|p| {
+        let share = p.amount.amount.saturating_sub(p.refunded.amount).min(left);
+        left -= share;
+        (share > 0).then(|| (p.id, Money::new(share, amount.currency)))
+      })
+      .collect();
+
+    let mut refunded = Money::zero(amount.currency);
+    let mut payment_ids = Vec::with_capacity(shares.len());
+    for (id, share) in shares {
+      if let Err(error) = self.refund_payment(id, share, provider) {
+        if payment_ids.is_empty() {
+          return Err(error);
+        }
+        return Err(Error::PartiallyRefunded {
+          payment_ids,
+          refunded,
+          error: Box::new(error),
+        });
+      }
+      // The shares add up to at most `amount`.
+      refunded.amount += share.amount;
+      payment_ids.push(id);
+    }
+    Ok(payment_ids)
+  }
+
+  fn follow_total(
+    &mut self,
+    order_id: order::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error> {
+    let total = self
+      .orders
+      .iter()
+      .find(|o| o.id == order_id)
+      .ok_or(Error::OrderNotFound)?
+      .total;
+    let Some(open) = self
+      .payments
+      .iter_mut()
+      .find(|p| p.order_id == order_id && matches!(p.status, Status::Created | Status::Authorized))
+    else {
+      return Ok(());
+    };
+    if let (Status::Created, Some(total)) = (open.status, total) {
+      open.amount = total;
+      open.refunded = Money::zero(total.currency);
+      return Ok(());
+    }
+    let id = open.id;
+    self.void_payment(id, provider)
+  }
+
+  fn void_payment(
+    &mut self,
+    id: payment::Id,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<(), Error> {
+    let payment = find(&mut self.payments, id)?;
+    match (payment.status, &payment.reference) {
+      (Status::Created, _) => {}
+      (Status::Authorized, Some(reference)) => provider
+        .void(reference, payment.amount)
+        .map_err(Error::Provider)?,
+      (status, _) => return Err(Error::InvalidStatus(status)),
+    }
+    payment.status = Status::Voided;
+    Ok(())
+  }
+
+  fn charge_order(
+    &mut self,
+    order_id: order::Id,
+    amount: Money,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<payment::Id, Error> {
+    let order = self
+      .orders
+      .iter()
+      .find(|o| o.id == order_id)
+      .ok_or(Error::OrderNotFound)?;
+    if order.status != order::Status::Open {
+      return Err(Error::OrderCancelled);
+    }
+    let id = self.next_payment_id;
+    if self.payments.iter().any(|p| p.id == id) {
+      return Err(Error::DuplicateId);
+    }
+    self.next_payment_id = payment::Id(id.0.checked_add(1).ok_or(Error::IdsExhausted)?);
+    self.payments.push(PaymentIntent {
+      id,
+      order_id,
+      amount,
+      status: Status::Created,
+      reference: None,
+      refunded: Money::zero(amount.currency),
+    });
+    self.authorize_payment(id, provider)?;
+    if let Err(error) = self.capture_payment(id, provider) {
+      if find(&mut self.payments, id)?.status == Status::Authorized {
+        let _ = self.void_payment(id, provider);
+      }
+      return Err(error);
+    }
+    Ok(id)
+  }
+}
+
+fn find(payments: &mut [PaymentIntent], id: payment::Id) -> Result<&mut PaymentIntent, Error> {
+  payments
+    .iter_mut()
+    .find(|p| p.id == id)
+    .ok_or(Error::PaymentNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      fixtures::{ordered, ticketed},
+      money::Currency,
+      payment_provider::FakeProvider,
+    },
+  };
+
+  fn statuses(database: &Database) -> Vec<seat_reservation::Status> {
+    database
+      .seat_reservations
+      .iter()
+      .map(seat_reservation::SeatReservation::status)
+      .collect()
+  }
+
+  #[test]
+  fn captured_payment_tickets_the_order() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+
+    assert_eq!(
+      database.authorize_payment(payment::Id(0), &mut provider),
+      Ok(())
+    );
+    assert_eq!(statuses(&database), [seat_reservation::Status::Held; 2]);
+    assert_eq!(
+      database.capture_payment(payment::Id(0), &mut provider),
+      Ok(())
+    );
+    assert_eq!(statuses(&database), [seat_reservation::Status::Ticketed; 2]);
+    assert_eq!(
+      provider.calls[1],
+      (
+        "capture",
+        "fake-0".to_string(),
+        Money::new(2_000, Currency::XXX)
+      )
+    );
+    assert_eq!(
+      database.refund_payment(
+        payment::Id(0),
+        Money::new(2_001, Currency::XXX),
+        &mut provider
+      ),
+      Err(Error::RefundTooLarge)
+    );
+    assert_eq!(
+      database.refund_payment(
+        payment::Id(0),
+        Money::new(500, Currency::XXX),
+        &mut provider
+      ),
+      Ok(())
+    );
+    assert_eq!(database.payments[0].status, Status::PartiallyRefunded);
+    assert_eq!(database.payments[0].refunded.amount, 500);
+    assert_eq!(
+      database.refund_payment(
+        payment::Id(0),
+        Money::new(1_500, Currency::XXX),
+        &mut provider
+      ),
+      Ok(())
+    );
+    assert_eq!(database.payments[0].status, Status::Refunded);
+    assert_eq!(
+      database.refund_payment(payment::Id(0), Money::new(1, Currency::XXX), &mut provider),
+      Err(Error::InvalidStatus(Status::Refunded))
+    );
+  }
+
+  #[test]
+  fn decline_then_retry() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    provider.fail_next(Failure::Declined);
+
+    assert_eq!(
+      database.authorize_payment(payment::Id(0), &mut provider),
+      Err(Error::Provider(Failure::Declined))
+    );
+    assert_eq!(
+      database.capture_payment(payment::Id(0), &mut provider),
+      Err(Error::InvalidStatus(Status::Failed(Failure::Declined)))
+    );
+    assert_eq!(
+      database.create_payment(Form {
+        id: payment::Id(1),
+        order_id: order::Id(0),
+      }),
+      Ok(())
+    );
+    assert_eq!(
+      database.create_payment(Form {
+        id: payment::Id(2),
+        order_id: order::Id(0),
+      }),
+      Err(Error::AlreadyPaid(payment::Id(1)))
+    );
+    assert_eq!(
+      database.authorize_payment(payment::Id(1), &mut provider),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn capture_timeout_can_be_retried() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    provider.succeed_next();
+    provider.fail_next(Failure::TimedOut);
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+
+    assert_eq!(
+      database.capture_payment(payment::Id(0), &mut provider),
+      Err(Error::Provider(Failure::TimedOut))
+    );
+    assert_eq!(database.payments[0].status, Status::Authorized);
+    assert_eq!(statuses(&database), [seat_reservation::Status::Held; 2]);
+    assert_eq!(
+      database.capture_payment(payment::Id(0), &mut provider),
+      Ok(())
+    );
+    assert_eq!(statuses(&database), [seat_reservation::Status::Ticketed; 2]);
+  }
+
+  #[test]
+  fn authorize_timeout_keeps_the_payment_open() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    provider.fail_next(Failure::TimedOut);
+
+    assert_eq!(
+      database.authorize_payment(payment::Id(0), &mut provider),
+      Err(Error::Provider(Failure::TimedOut))
+    );
+    assert_eq!(database.payments[0].status, Status::Created);
+    assert_eq!(
+      database.create_payment(Form {
+        id: payment::Id(1),
+        order_id: order::Id(0),
+      }),
+      Err(Error::AlreadyPaid(payment::Id(0)))
+    );
+  }
+
+  #[test]
+  fn charges_take_ids_past_every_payment() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    let _ = database.void_payment(payment::Id(0), &mut provider);
+    let _ = database.create_payment(Form {
+      id: payment::Id(7),
+      order_id: order::Id(0),
+    });
+    let _ = database.authorize_payment(payment::Id(7), &mut provider);
+    let _ = database.capture_payment(payment::Id(7), &mut provider);
+
+    assert_eq!(
+      database.charge_order(order::Id(0), Money::new(100, Currency::XXX), &mut provider),
+      Ok(payment::Id(8))
+    );
+    assert_eq!(
+      database.charge_order(order::Id(0), Money::new(100, Currency::XXX), &mut provider),
+      Ok(payment::Id(9))
+    );
+  }
+
+  #[test]
+  fn charges_need_an_open_order_and_a_free_id() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    let charge = Money::new(100, Currency::XXX);
+    database.next_payment_id = payment::Id(0);
+
+    assert_eq!(
+      database.charge_order(order::Id(0), charge, &mut provider),
+      Err(Error::DuplicateId)
+    );
+    database.next_payment_id = payment::Id(u64::MAX);
+    assert_eq!(
+      database.charge_order(order::Id(0), charge, &mut provider),
+      Err(Error::IdsExhausted)
+    );
+    database.orders[0].status = order::Status::Cancelled;
+    assert_eq!(
+      database.charge_order(order::Id(0), charge, &mut provider),
+      Err(Error::OrderCancelled)
+    );
+    assert_eq!(provider.calls.len(), 2);
+  }
+
+  #[test]
+  fn voided_payment_can_be_replaced() {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    provider.fail_next(Failure::TimedOut);
+
+    assert_eq!(
+      database.void_payment(payment::Id(0), &mut provider),
+      Err(Error::Provider(Failure::TimedOut))
+    );
+    assert_eq!(database.payments[0].status, Status::Authorized);
+    assert_eq!(database.void_payment(payment::Id(0), &mut provider), Ok(()));
+    assert_eq!(database.payments[0].status, Status::Voided);
+    assert_eq!(
+      provider.calls[1],
+      (
+        "void",
+        "fake-0".to_string(),
+        Money::new(2_000, Currency::XXX)
+      )
+    );
+    assert_eq!(
+      database.capture_payment(payment::Id(0), &mut provider),
+      Err(Error::InvalidStatus(Status::Voided))
+    );
+    assert_eq!(
+      database.create_payment(Form {
+        id: payment::Id(1),
+        order_id: order::Id(0),
+      }),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn failed_refund_after_another_is_partial() {
+    let mut provider = FakeProvider::default();
+    let mut database = ticketed(&mut provider);
+    let _ = database.charge_order(order::Id(0), Money::new(500, Currency::XXX), &mut provider);
+    provider.succeed_next();
+    provider.fail_next(Failure::Declined);
+
+    assert_eq!(
+      database.refund_order(
+        order::Id(0),
+        Money::new(2_200, Currency::XXX),
+        &mut provider
+      ),
+      Err(Error::PartiallyRefunded {
+        payment_ids: vec![payment::Id(1)],
+        refunded: Money::new(500, Currency::XXX),
+        error: Box::new(Error::Provider(Failure::Declined)),
+      })
+    );
+    assert_eq!(database.payments[1].status, Status::Refunded);
+    assert_eq!(database.payments[0].status, Status::Captured);
+    assert_eq!(
+      database.refundable(order::Id(0)),
+      Some(Money::new(2_000, Currency::XXX))
+    );
+  }
+}
@@ -0,0 +1,62 @@
+use {
+  crate::{discount::Discount, route, time::Timestamp},
+  std::{error, fmt::Display},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PromoCode {
+  /// Matched without regard to ASCII case.
+  pub code: String,
+  pub discount: Discount,
+  /// Bookings requested in `valid_from..valid_until` may use the code.
+  pub valid_from: Timestamp,
+  pub valid_until: Timestamp,
+  pub max_uses: Option<u32>,
+  pub uses: u32,
+  /// Routes the code is limited to; empty means any route.
+  pub route_ids: Vec<route::Id>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+  NotFound,
+  NotYetValid,
+  Expired,
+  Exhausted,
+  NotValidForRoute,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NotFound => write!(f, "promo code not found"),
+      Self::NotYetValid => write!(f, "promo code is not valid yet"),
+      Self::Expired => write!(f, "promo code has expired"),
+      Self::Exhausted => write!(f, "promo code has no uses left"),
+      Self::NotValidForRoute => write!(f, "promo code does not apply to this route"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+impl PromoCode {
+  pub fn matches(&self, code: &str) -> bool {
+    self.code.eq_ignore_ascii_case(code)
+  }
+
+  pub fn check(&self, route_id: route::Id, at: Timestamp) -> Result<(), Error> {
+    if at < self.valid_from {
+      return Err(Error::NotYetValid);
+    }
+    if at >= self.valid_until {
+      return Err(Error::Expired);
+    }
+    if self.max_uses.is_some_and(|max| self.uses >= max) {
+      return Err(Error::Exhausted);
+    }
+    if !self.route_ids.is_empty() && !self.route_ids.contains(&route_id) {
+      return Err(Error::NotValidForRoute);
+    }
+    Ok(())
+  }
+}
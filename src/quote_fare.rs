@@ -0,0 +1,583 @@
+use {
+  crate::{
+    fare::{DepartureTier, LoadTier, PricingPolicy},
+    fare_type,
+    money::{Currency, Exact, Money},
+    promo_code,
+    reserve_seat::{self, Aggregate, ReserveSeat},
+    show_route_legs::ShowRouteLegs,
+    Database,
+  },
+  std::{error, fmt::Display, time::Duration},
+};
+
+/// Price of a reservation as line items, all in the same currency. Each line
+/// is rounded once from its exact amount and the total is added up from the
+/// rounded lines, so they always match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quote {
+  /// Share of seats taken on the busiest requested segment, before this
+  /// booking.
+  pub load_percent: u8,
+  /// The route's `PricingPolicy` tiers that were applied, as they stood
+  /// when quoted.
+  pub load_tier: Option<LoadTier>,
+  pub departure_tier: Option<DepartureTier>,
+  /// Route fare for the stops after the pricing tiers.
+  pub base: Money,
+  /// Reduced fare the passenger travels on, if any.
+  pub fare_type_id: Option<fare_type::Id>,
+  /// Taken off `base` by the fare type and promo code.
+  pub discount: Money,
+  /// One line per route tax rule, charged on the discounted fare.
+  pub taxes: Vec<Tax>,
+  /// What the passenger pays.
+  pub total: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tax {
+  pub name: String,
+  pub amount: Money,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  Reservation(reserve_seat::Error),
+  FareNotFound,
+  FareTypeNotFound,
+  PromoCode(promo_code::Error),
+  CurrencyNotOffered(Currency),
+  /// A fixed discount is in neither the sale's nor the route's currency.
+  CurrencyMismatch,
+  /// An amount does not fit in minor units.
+  OutOfRange,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Reservation(error) => write!(f, "reservation not possible: {error}"),
+      Self::FareNotFound => write!(f, "no fare for these stops"),
+      Self::FareTypeNotFound => write!(f, "fare type not found"),
+      Self::PromoCode(error) => write!(f, "{error}"),
+      Self::CurrencyNotOffered(currency) => write!(f, "route is not sold in {currency}"),
+      Self::CurrencyMismatch => write!(f, "discount is in another currency"),
+      Self::OutOfRange => write!(f, "price is out of range"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+impl From<reserve_seat::Error> for Error {
+  fn from(error: reserve_seat::Error) -> Self {
+    match error {
+      reserve_seat::Error::FareTypeNotFound => Self::FareTypeNotFound,
+      reserve_seat::Error::InvalidPromoCode(error) => Self::PromoCode(error),
+      error => Self::Reservation(error),
+    }
+  }
+}
+
+pub trait QuoteFare {
+  /// Prices a reservation without making it. The form must be one
+  /// `reserve_seat` would accept right now.
+  #[allow(dead_code)]
+  fn quote_fare(&self, form: &reserve_seat::Form) -> Result<Quote, Error>;
+
+  /// Prices an already checked form against the trip's current occupancy.
+  fn price(&self, form: &reserve_seat::Form, aggr: &Aggregate) -> Result<Quote, Error>;
+}
+
+impl QuoteFare for Database {
+  fn quote_fare(&self, form: &reserve_seat::Form) -> Result<Quote, Error> {
+    self.check_form(form)?;
+    let aggr = self.build_aggregate(form.trip_id)?;
+    self.price(form, &aggr)
+  }
+
+  fn price(&self, form: &reserve_seat::Form, aggr: &Aggregate) -> Result<Quote, Error> {
+    let (from, to) = (form.from_stop_index, form.to_stop_index);
+    let route = aggr.route();
+    let distance = self.route_distance(route.id, from, to);
+    let fare = route
+      .fare
+      .as_ref()
+      .and_then(|fare| fare.price(from, to, distance, route.currency))
+      .ok_or(Error::FareNotFound)?;
+    let rate = match form.currency {
+      Some(currency) if currency != route.currency => Some(
+        route
+          .exchange_rates
+          .iter()
+          .find(|r| r.currency() == currency)
+          .ok_or(Error::CurrencyNotOffered(currency))?,
+      ),
+      _ => None,
+    };
+    let fare = match rate {
+      Some(rate) => rate.convert(fare).ok_or(Error::OutOfRange)?,
+      None => fare,
+    };
+    // Amounts in the route's currency follow the fare into the sale's.
+    let convert = route.convert_to(fare.currency);
+
+    let load_percent = self
+      .build_occupancy(form.trip_id, aggr)?
+      .load_percent(from, to);
+    let until_departure = aggr
+      .until_departure(from, form.requested_at)
+      .unwrap_or(Duration::ZERO);
+    let load_tier = route.pricing.load_tier(load_percent);
+    let departure_tier = route.pricing.departure_tier(until_departure);
+    let base = PricingPolicy::apply(fare, load_tier, departure_tier).ok_or(Error::OutOfRange)?;
+
+    let mut discounts = vec![];
+    if let Some(fare_type_id) = form.fare_type_id {
+      let fare_type = self
+        .fare_types
+        .iter()
+        .find(|ft| ft.id == fare_type_id)
+        .ok_or(Error::FareTypeNotFound)?;
+      discounts.push(fare_type.discount);
+    }
+    if let Some(code) = &form.promo_code {
+      let promo_code = self
+        .promo_codes
+        .iter()
+        .find(|pc| pc.matches(code))
+        .ok_or(Error::PromoCode(promo_code::Error::NotFound))?;
+      promo_code
+        .check(route.id, form.requested_at)
+        .map_err(Error::PromoCode)?;
+      discounts.push(promo_code.discount);
+    }
+    let mut net = base;
+    for discount in discounts {
+      let off = discount
+        .exact_off(net, &convert)
+        .ok_or(Error::CurrencyMismatch)?;
+      net = net.checked_sub(off).ok_or(Error::OutOfRange)?;
+    }
+
+    let taxes: Vec<(String, Exact)> = route
+      .tax_rules
+      .iter()
+      .map(|rule| {
+        net
+          .scale(u64::from(rule.basis_points), 10_000)
+          .map(|amount| (rule.name.clone(), amount))
+          .ok_or(Error::OutOfRange)
+      })
+      .collect::<Result<_, _>>()?;
+
+    let round = |amount: Exact| amount.round(route.rounding).ok_or(Error::OutOfRange);
+    let taxes: Vec<Tax> = taxes
+      .into_iter()
+      .map(|(name, amount)| {
+        Ok(Tax {
+          name,
+          amount: round(amount)?,
+        })
+      })
+      .collect::<Result<_, Error>>()?;
+    let (base, discount) = (
+      round(base)?,
+      round(base.checked_sub(net).ok_or(Error::OutOfRange)?)?,
+    );
+
+    Ok(Quote {
+      load_percent,
+      load_tier,
+      departure_tier,
+      base,
+      fare_type_id: form.fare_type_id,
+      discount,
+      total: total(base, discount, &taxes).ok_or(Error::OutOfRange)?,
+      taxes,
+    })
+  }
+}
+
+/// `base` less `discount` plus every tax line.
+fn total(base: Money, discount: Money, taxes: &[Tax]) -> Option<Money> {
+  taxes
+    .iter()
+    .try_fold(base.checked_sub(discount)?, |total, tax| {
+      total.checked_add(tax.amount)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      discount::Discount,
+      fare::{DepartureTier, Fare, LoadTier, OdFare, PricingPolicy},
+      fare_type::{self, FareType},
+      fixtures::database,
+      money::{ExchangeRate, Rounding, TaxRule},
+      promo_code::PromoCode,
+      route,
+      time::Timestamp,
+    },
+  };
+
+  #[test]
+  fn od_matrix() {
+    let mut database = database();
+    database.route_stops.push(route::Stop {
+      index: 2,
+      ..Default::default()
+    });
+    database.routes[0].fare = Some(Fare::Matrix(vec![OdFare {
+      from: 0,
+      to: 1,
+      amount: 4_500,
+    }]));
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+    let missing = reserve_seat::Form {
+      from_stop_index: 1,
+      to_stop_index: 2,
+      ..form.clone()
+    };
+
+    assert_eq!(
+      database.quote_fare(&form).map(|q| q.total.amount),
+      Ok(4_500)
+    );
+    assert_eq!(database.quote_fare(&missing), Err(Error::FareNotFound));
+    assert!(database.seat_reservations.is_empty());
+  }
+
+  #[test]
+  fn by_distance() {
+    let mut database = database();
+    database.routes[0].fare = Some(Fare::Distance {
+      base: 1_000,
+      per_km: 35,
+    });
+    database.route_stops[1].leg_distance_meters = 87_300;
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.quote_fare(&form).map(|q| q.total.amount),
+      Ok(4_056)
+    );
+  }
+
+  #[test]
+  fn rejects_unreservable_forms() {
+    let mut database = database();
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+    let _ = database.reserve_seat(form.clone());
+
+    assert_eq!(
+      database.quote_fare(&form),
+      Err(Error::Reservation(reserve_seat::Error::SeatAlreadyReserved))
+    );
+  }
+
+  #[test]
+  fn load_and_departure_tiers() {
+    let mut database = database();
+    database.bus_models[0].number_of_seats = 4;
+    database.trips[0].departure = Timestamp(100_000);
+    database.routes[0].fare = Some(Fare::Matrix(vec![OdFare {
+      from: 0,
+      to: 1,
+      amount: 1_000,
+    }]));
+    let full = LoadTier {
+      min_load_percent: 75,
+      price_percent: 150,
+    };
+    let half = LoadTier {
+      min_load_percent: 50,
+      price_percent: 120,
+    };
+    let last_minute = DepartureTier {
+      within: Duration::from_hours(2),
+      price_percent: 130,
+    };
+    database.routes[0].pricing = PricingPolicy {
+      load_tiers: vec![full, half],
+      departure_tiers: vec![
+        DepartureTier {
+          within: Duration::from_hours(24),
+          price_percent: 110,
+        },
+        last_minute,
+      ],
+    };
+    for seat_index in 0..3 {
+      let _ = database.reserve_seat(reserve_seat::Form {
+        seat_index,
+        to_stop_index: 1,
+        ..Default::default()
+      });
+    }
+    let amounts: Vec<_> = database
+      .seat_reservations
+      .iter()
+      .map(|sr| {
+        sr.quote()
+          .map(|q| (q.load_percent, q.load_tier, q.total.amount))
+      })
+      .collect();
+
+    assert_eq!(
+      amounts,
+      [
+        Some((0, None, 1_000)),
+        Some((25, None, 1_000)),
+        Some((50, Some(half), 1_200))
+      ]
+    );
+    assert_eq!(
+      database.quote_fare(&reserve_seat::Form {
+        seat_index: 3,
+        to_stop_index: 1,
+        requested_at: Timestamp(100_000 - 3_600),
+        ..Default::default()
+      }),
+      Ok(Quote {
+        load_percent: 75,
+        load_tier: Some(full),
+        departure_tier: Some(last_minute),
+        base: Money::new(1_950, Currency::XXX),
+        fare_type_id: None,
+        discount: Money::new(0, Currency::XXX),
+        taxes: vec![],
+        total: Money::new(1_950, Currency::XXX),
+      })
+    );
+    database.routes[0].pricing = PricingPolicy::default();
+    assert_eq!(
+      database.seat_reservations[2]
+        .quote()
+        .and_then(|q| q.load_tier),
+      Some(half)
+    );
+  }
+
+  #[test]
+  fn priced_route_needs_a_fare_for_the_stops() {
+    let mut database = database();
+    database.routes[0].fare = Some(Fare::Matrix(vec![]));
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.reserve_seat(form),
+      Err(reserve_seat::Error::FareNotFound)
+    );
+    assert!(database.seat_reservations.is_empty());
+  }
+
+  fn discounted_database() -> Database {
+    let mut database = database();
+    database.bus_models[0].number_of_seats = 4;
+    database.trips[0].departure = Timestamp(10_000);
+    database.routes[0].fare = Some(Fare::Matrix(vec![OdFare {
+      from: 0,
+      to: 1,
+      amount: 1_000,
+    }]));
+    database.fare_types = vec![FareType {
+      id: fare_type::Id(0),
+      name: "Student".to_string(),
+      discount: Discount::Percent(25),
+    }];
+    database.promo_codes = vec![PromoCode {
+      code: "SUMMER".to_string(),
+      discount: Discount::Fixed(Money::new(100, Currency::XXX)),
+      valid_from: Timestamp(0),
+      valid_until: Timestamp(1_000),
+      max_uses: Some(1),
+      uses: 0,
+      route_ids: vec![route::Id(0)],
+    }];
+    database
+  }
+
+  #[test]
+  fn fare_type_and_promo_code() {
+    let mut database = discounted_database();
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      fare_type_id: Some(fare_type::Id(0)),
+      promo_code: Some("summer".to_string()),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database
+        .quote_fare(&form)
+        .map(|q| (q.discount.amount, q.total.amount)),
+      Ok((350, 650))
+    );
+    assert_eq!(database.reserve_seat(form.clone()), Ok(()));
+    assert_eq!(database.promo_codes[0].uses, 1);
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        seat_index: 1,
+        ..form
+      }),
+      Err(reserve_seat::Error::InvalidPromoCode(
+        promo_code::Error::Exhausted
+      ))
+    );
+  }
+
+  #[test]
+  fn unpriced_route_checks_discounts() {
+    let mut database = discounted_database();
+    database.routes[0].fare = None;
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        fare_type_id: Some(fare_type::Id(7)),
+        ..form.clone()
+      }),
+      Err(reserve_seat::Error::FareTypeNotFound)
+    );
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        promo_code: Some("SUMMER".to_string()),
+        requested_at: Timestamp(1_000),
+        ..form.clone()
+      }),
+      Err(reserve_seat::Error::InvalidPromoCode(
+        promo_code::Error::Expired
+      ))
+    );
+    assert_eq!(
+      database.reserve_seat(reserve_seat::Form {
+        promo_code: Some("SUMMER".to_string()),
+        ..form
+      }),
+      Ok(())
+    );
+    assert_eq!(database.promo_codes[0].uses, 1);
+  }
+
+  #[test]
+  fn invalid_discounts() {
+    let mut database = discounted_database();
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    };
+    let with_code = |code: &str, requested_at: i64| reserve_seat::Form {
+      promo_code: Some(code.to_string()),
+      requested_at: Timestamp(requested_at),
+      ..form.clone()
+    };
+
+    assert_eq!(
+      database.quote_fare(&reserve_seat::Form {
+        fare_type_id: Some(fare_type::Id(7)),
+        ..form.clone()
+      }),
+      Err(Error::FareTypeNotFound)
+    );
+    assert_eq!(
+      database.quote_fare(&with_code("WINTER", 0)),
+      Err(Error::PromoCode(promo_code::Error::NotFound))
+    );
+    assert_eq!(
+      database.quote_fare(&with_code("SUMMER", 1_000)),
+      Err(Error::PromoCode(promo_code::Error::Expired))
+    );
+    database.promo_codes[0].route_ids = vec![route::Id(1)];
+    assert_eq!(
+      database.quote_fare(&with_code("SUMMER", 0)),
+      Err(Error::PromoCode(promo_code::Error::NotValidForRoute))
+    );
+  }
+
+  #[test]
+  fn line_items_in_another_currency() {
+    let (clp, ars) = (Currency(*b"CLP"), Currency(*b"ARS"));
+    let mut database = discounted_database();
+    let route = &mut database.routes[0];
+    route.fare = Some(Fare::Matrix(vec![OdFare {
+      from: 0,
+      to: 1,
+      amount: 12_345,
+    }]));
+    route.currency = clp;
+    route.exchange_rates = ExchangeRate::new(ars, 1, 10).into_iter().collect();
+    route.tax_rules = vec![TaxRule {
+      name: "IVA".to_string(),
+      basis_points: 1_900,
+    }];
+    route.rounding = Rounding::HalfEven;
+    let form = reserve_seat::Form {
+      to_stop_index: 1,
+      fare_type_id: Some(fare_type::Id(0)),
+      currency: Some(ars),
+      ..Default::default()
+    };
+    let quote = database.quote_fare(&form);
+
+    // 1 234.5 less 308.625 plus 175.916 25 tax, each line rounded once and
+    // the total added up from the rounded lines.
+    assert_eq!(
+      quote.as_ref().map(|q| (q.base, q.discount, q.total)),
+      Ok((
+        Money::new(1_234, ars),
+        Money::new(309, ars),
+        Money::new(1_101, ars)
+      ))
+    );
+    assert_eq!(
+      quote.map(|q| q.taxes),
+      Ok(vec![Tax {
+        name: "IVA".to_string(),
+        amount: Money::new(176, ars),
+      }])
+    );
+    assert_eq!(
+      database.quote_fare(&reserve_seat::Form {
+        currency: Some(Currency(*b"USD")),
+        ..form.clone()
+      }),
+      Err(Error::CurrencyNotOffered(Currency(*b"USD")))
+    );
+    assert_eq!(
+      database.quote_fare(&reserve_seat::Form {
+        promo_code: Some("SUMMER".to_string()),
+        ..form.clone()
+      }),
+      Err(Error::CurrencyMismatch)
+    );
+    database.promo_codes[0].discount = Discount::Fixed(Money::new(1_000, clp));
+    assert_eq!(
+      database
+        .quote_fare(&reserve_seat::Form {
+          promo_code: Some("SUMMER".to_string()),
+          ..form
+        })
+        .map(|q| (q.discount, q.total)),
+      Ok((Money::new(409, ars), Money::new(982, ars)))
+    );
+  }
+}
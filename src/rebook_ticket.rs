@@ -0,0 +1,502 @@
+use {
+  crate::{
+    money::Money,
+    order,
+    payment_provider::PaymentProvider,
+    place_order::PlaceOrder,
+    process_payment::{self, ProcessPayment},
+    reserve_seat::{self, ReserveSeat},
+    seat_reservation::{self, SeatReservation},
+    show_payment::ShowPayment,
+    show_ticket::ShowTicket,
+    trip, Database,
+  },
+  std::{error, fmt::Display},
+};
+
+pub struct Form {
+  pub trip_id: trip::Id,
+  pub seat_index: usize,
+  /// Any stop the passenger leaves on board, from boarding on.
+  pub stop_index: usize,
+  /// The new seat. Passenger and currency default to the current ticket's;
+  /// the order, when given, must be the ticket's own.
+  pub new: reserve_seat::Form,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  TicketNotFound,
+  /// The seat is only held; change it through its order.
+  NotTicketed,
+  TripDeparted,
+  /// The new seat would go to another order than the ticket's.
+  OrderMismatch,
+  /// Too close to departure for the route's change policy.
+  ChangeNotAllowed,
+  Reservation(reserve_seat::Error),
+  CurrencyMismatch,
+  /// The new seat costs more and the order has no captured payment to
+  /// charge the difference to.
+  NotPaid,
+  Payment(process_payment::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TicketNotFound => write!(f, "ticket not found"),
+      Self::NotTicketed => write!(f, "seat is held, not ticketed"),
+      Self::TripDeparted => write!(f, "trip has departed"),
+      Self::OrderMismatch => write!(f, "ticket belongs to another order"),
+      Self::ChangeNotAllowed => write!(f, "ticket can no longer be changed"),
+      Self::Reservation(error) => write!(f, "new seat cannot be reserved: {error}"),
+      Self::CurrencyMismatch => write!(f, "new fare or change fee is in another currency"),
+      Self::NotPaid => write!(f, "order has no payment to charge the difference to"),
+      Self::Payment(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Exchange {
+  /// The released reservation.
+  pub previous: SeatReservation,
+  pub fee: Money,
+  /// New fare plus fee not covered by the old fare, taken with a new
+  /// payment.
+  pub charged: Money,
+  /// Paid back through the order's captured payments.
+  pub refunded: Money,
+  /// Cheaper fare the change policy does not pay back, which stays in the
+  /// order's total.
+  pub kept: Money,
+}
+
+#[allow(dead_code)]
+pub trait RebookTicket {
+  /// Moves a ticket to another trip, seat or stops in one step. The new
+  /// seat is reserved with the current one released, so it may overlap it,
+  /// and is ticketed once any difference is paid; if anything fails the
+  /// current seat is restored. A charge is handled as by `charge_order`. A
+  /// refund that fails after part of it was returned keeps the new ticket
+  /// and reports what was refunded.
+  fn rebook_ticket(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Exchange, Error>;
+}
+
+impl RebookTicket for Database {
+  fn rebook_ticket(
+    &mut self,
+    form: Form,
+    provider: &mut dyn PaymentProvider,
+  ) -> Result<Exchange, Error> {
+    let position = self
+      .find_ticket(form.trip_id, form.seat_index, form.stop_index)
+      .ok_or(Error::TicketNotFound)?;
+    let current = &self.seat_reservations[position];
+    if current.status() != seat_reservation::Status::Ticketed {
+      return Err(Error::NotTicketed);
+    }
+
+    let aggr = self
+      .build_aggregate(form.trip_id)
+      .map_err(Error::Reservation)?;
+    let until_departure = aggr
+      .until_departure(current.first_stop(), form.new.requested_at)
+      .ok_or(Error::TripDeparted)?;
+    let route = aggr.route();
+    let paid = current
+      .quote()
+      .map_or_else(|| Money::zero(route.currency), |quote| quote.total);
+    let fee = route
+      .change
+      .fee(
+        paid,
+        until_departure,
+        route.rounding,
+        route.convert_to(paid.currency),
+      )
+      .map_err(|_| Error::CurrencyMismatch)?
+      .ok_or(Error::ChangeNotAllowed)?;
+    let refund_difference = route.change.refund_difference;
+    let order_id = current.order_id();
+    if form.new.order_id.is_some_and(|id| Some(id) != order_id) {
+      return Err(Error::OrderMismatch);
+    }
+    // The order is paid, so the seat joins it once reserved.
+    let new = reserve_seat::Form {
+      passenger_id: form.new.passenger_id.or_else(|| current.passenger_id()),
+      order_id: None,
+      currency: form
+        .new
+        .currency
+        .or_else(|| current.quote().map(|q| q.total.currency)),
+      ..form.new
+    };
+
+    let previous = self.seat_reservations.remove(position);
+    let remaining = self.seat_reservations.len();
+    let promo_uses: Vec<u32> = self.promo_codes.iter().map(|pc| pc.uses).collect();
+    self.release_promo_code(&previous);
+    let result = self
+      .reserve_seat(new)
+      .map_err(Error::Reservation)
+      .map(|()| {
+        if let Some(rebooked) = self.seat_reservations.last_mut() {
+          rebooked.set_order_id(order_id);
+        }
+      })
+      .and_then(|()| settle(self, &previous, paid, fee, refund_difference, provider));
+
+    match result {
+      Ok((charged, refunded, kept)) => {
+        ticket_rebooked(self, order_id, kept);
+        Ok(Exchange {
+          previous,
+          fee,
+          charged,
+          refunded,
+          kept,
+        })
+      }
+      Err(error @ Error::Payment(process_payment::Error::PartiallyRefunded { .. })) => {
+        ticket_rebooked(self, order_id, Money::zero(paid.currency));
+        Err(error)
+      }
+      Err(error) => {
+        self.seat_reservations.truncate(remaining);
+        for (promo_code, uses) in self.promo_codes.iter_mut().zip(promo_uses) {
+          promo_code.uses = uses;
+        }
+        self.seat_reservations.insert(position, previous);
+        Err(error)
+      }
+    }
+  }
+}
+
+/// Tickets the just reserved seat and sets the order's total to its fares
+/// plus what the change policy kept of a cheaper one.
+fn ticket_rebooked(database: &mut Database, order_id: Option<order::Id>, kept: Money) {
+  if let Some(rebooked) = database.seat_reservations.last_mut() {
+    rebooked.set_status(seat_reservation::Status::Ticketed);
+  }
+  let Some(order_id) = order_id else {
+    return;
+  };
+  let total = database
+    .order_total(order_id)
+    .ok()
+    .flatten()
+    .map(|total| total.checked_add(kept).unwrap_or(total));
+  if let Some(order) = database.orders.iter_mut().find(|o| o.id == order_id) {
+    order.total = total;
+  }
+}
+
+/// `(charged, refunded, kept)` given the just reserved seat's fare. What is
+/// owed on top of the old fare is charged with a new payment; a cheaper
+/// fare is paid back through the order's captured payments when the policy
+/// allows it, and kept otherwise.
+fn settle(
+  database: &mut Database,
+  previous: &SeatReservation,
+  paid: Money,
+  fee: Money,
+  refund_difference: bool,
+  provider: &mut dyn PaymentProvider,
+) -> Result<(Money, Money, Money), Error> {
+  let zero = Money::zero(paid.currency);
+  let fare = database
+    .seat_reservations
+    .last()
+    .and_then(SeatReservation::quote)
+    .map_or(zero, |quote| quote.total);
+  let owed = fare.checked_add(fee).ok_or(Error::CurrencyMismatch)?;
+  let order_id = previous
+    .order_id()
+    .filter(|&order_id| database.captured_payment(order_id).is_some());
+  if let Some(due) = owed.checked_sub(paid) {
+    if due.amount == 0 {
+      return Ok((zero, zero, zero));
+    }
+    database
+      .charge_order(order_id.ok_or(Error::NotPaid)?, due, provider)
+      .map_err(Error::Payment)?;
+    return Ok((due, zero, zero));
+  }
+  let difference = paid.checked_sub(owed).ok_or(Error::CurrencyMismatch)?;
+  let (true, Some(order_id)) = (refund_difference, order_id) else {
+    return Ok((zero, zero, difference));
+  };
+
+  database
+    .refund_order(order_id, difference, provider)
+    .map_err(Error::Payment)?;
+  Ok((zero, difference, zero))
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      cancellation::{ChangePolicy, FeeWindow},
+      discount::Discount,
+      fare::{Fare, OdFare},
+      fare_type::{self, FareType},
+      fixtures::{ordered, ticketed},
+      money::Currency,
+      payment::{self, Failure},
+      payment_provider::FakeProvider,
+      seat_reservation,
+      time::Timestamp,
+    },
+    std::time::Duration,
+  };
+
+  /// The paid test order, with changes costing 100 until a day before
+  /// departure and a 25 % student fare.
+  fn changeable(provider: &mut FakeProvider) -> Database {
+    let mut database = ticketed(provider);
+    database.routes[0].change = ChangePolicy {
+      windows: vec![FeeWindow {
+        before: Duration::from_hours(24),
+        fee: Discount::Fixed(Money::new(100, Currency::XXX)),
+      }],
+      refund_difference: true,
+    };
+    database.fare_types = vec![FareType {
+      id: fare_type::Id(0),
+      name: "Student".to_string(),
+      discount: Discount::Percent(25),
+    }];
+    database
+  }
+
+  fn form(trip_id: u64, seat_index: usize, requested_at: i64) -> Form {
+    Form {
+      trip_id: trip::Id(0),
+      seat_index: 0,
+      stop_index: 0,
+      new: reserve_seat::Form {
+        seat_index,
+        to_stop_index: 1,
+        trip_id: trip::Id(trip_id),
+        requested_at: Timestamp(requested_at),
+        fare_type_id: Some(fare_type::Id(0)),
+        ..Default::default()
+      },
+    }
+  }
+
+  #[test]
+  fn cheaper_fare_refunds_the_difference() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let exchange = database.rebook_ticket(form(1, 1, 0), &mut provider);
+
+    assert_eq!(
+      exchange.map(|e| (e.fee.amount, e.charged.amount, e.refunded.amount)),
+      Ok((100, 0, 150))
+    );
+    assert_eq!(database.payments[0].refunded.amount, 150);
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_750, Currency::XXX))
+    );
+    let rebooked = &database.seat_reservations[1];
+    assert_eq!((rebooked.trip_id(), rebooked.seat()), (trip::Id(1), 1));
+    assert_eq!(rebooked.status(), seat_reservation::Status::Ticketed);
+    assert_eq!(rebooked.order_id(), database.orders.first().map(|o| o.id));
+  }
+
+  #[test]
+  fn kept_difference_stays_in_the_total() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    database.routes[0].change.refund_difference = false;
+    let exchange = database.rebook_ticket(form(1, 1, 0), &mut provider);
+
+    assert_eq!(
+      exchange.map(|e| (e.refunded.amount, e.kept.amount)),
+      Ok((0, 150))
+    );
+    assert!(provider.calls.iter().all(|call| call.0 != "refund"));
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_900, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn partial_refund_keeps_the_new_ticket() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let _ = database.charge_order(order::Id(0), Money::new(100, Currency::XXX), &mut provider);
+    provider.succeed_next();
+    provider.fail_next(Failure::Declined);
+
+    assert_eq!(
+      database.rebook_ticket(form(1, 1, 0), &mut provider),
+      Err(Error::Payment(process_payment::Error::PartiallyRefunded {
+        payment_ids: vec![payment::Id(1)],
+        refunded: Money::new(100, Currency::XXX),
+        error: Box::new(process_payment::Error::Provider(Failure::Declined)),
+      }))
+    );
+    let rebooked = &database.seat_reservations[1];
+    assert_eq!((rebooked.trip_id(), rebooked.seat()), (trip::Id(1), 1));
+    assert_eq!(rebooked.status(), seat_reservation::Status::Ticketed);
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_750, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn seat_stays_in_the_ticket_order() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let before = database.seat_reservations.clone();
+    let mut other_order = form(1, 1, 0);
+    other_order.new.order_id = Some(order::Id(1));
+
+    assert_eq!(
+      database.rebook_ticket(other_order, &mut provider),
+      Err(Error::OrderMismatch)
+    );
+    assert_eq!(database.seat_reservations, before);
+  }
+
+  #[test]
+  fn same_seat_pays_the_fee() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let mut same_seat = form(0, 0, 0);
+    same_seat.new.fare_type_id = None;
+
+    assert_eq!(
+      database
+        .rebook_ticket(same_seat, &mut provider)
+        .map(|e| (e.charged.amount, e.refunded.amount)),
+      Ok((100, 0))
+    );
+    assert_eq!(
+      (
+        database.payments[1].amount.amount,
+        database.payments[1].status
+      ),
+      (100, payment::Status::Captured)
+    );
+    assert_eq!(
+      database.rebook_ticket(form(1, 1, 100_000 - 3_600), &mut provider),
+      Err(Error::ChangeNotAllowed)
+    );
+  }
+
+  #[test]
+  fn dearer_fare_charges_the_difference() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    database.routes[0].fare = Some(Fare::Matrix(vec![OdFare {
+      from: 0,
+      to: 1,
+      amount: 1_500,
+    }]));
+    let mut full_fare = form(1, 1, 0);
+    full_fare.new.fare_type_id = None;
+
+    assert_eq!(
+      database
+        .rebook_ticket(full_fare, &mut provider)
+        .map(|e| (e.fee.amount, e.charged.amount)),
+      Ok((100, 600))
+    );
+    assert_eq!(
+      provider.calls.last(),
+      Some(&(
+        "capture",
+        "fake-1".to_string(),
+        Money::new(600, Currency::XXX)
+      ))
+    );
+    assert_eq!(
+      database.seat_reservations[1].status(),
+      seat_reservation::Status::Ticketed
+    );
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(2_500, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn only_paid_tickets_are_rebooked() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let before = database.seat_reservations.clone();
+    database.payments.clear();
+    let mut same_seat = form(0, 0, 0);
+    same_seat.new.fare_type_id = None;
+
+    assert_eq!(
+      database.rebook_ticket(same_seat, &mut provider),
+      Err(Error::NotPaid)
+    );
+    assert_eq!(database.seat_reservations, before);
+
+    let mut database = ordered();
+    assert_eq!(
+      database.rebook_ticket(form(1, 1, 0), &mut provider),
+      Err(Error::NotTicketed)
+    );
+  }
+
+  #[test]
+  fn timed_out_capture_is_voided() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let before = database.seat_reservations.clone();
+    let mut same_seat = form(0, 0, 0);
+    same_seat.new.fare_type_id = None;
+    provider.succeed_next();
+    provider.fail_next(Failure::TimedOut);
+
+    assert_eq!(
+      database.rebook_ticket(same_seat, &mut provider),
+      Err(Error::Payment(process_payment::Error::Provider(
+        Failure::TimedOut
+      )))
+    );
+    assert_eq!(database.payments[1].status, payment::Status::Voided);
+    assert_eq!(provider.calls.last().map(|call| call.0), Some("void"));
+    assert_eq!(database.seat_reservations, before);
+  }
+
+  #[test]
+  fn failures_keep_the_current_seat() {
+    let mut provider = FakeProvider::default();
+    let mut database = changeable(&mut provider);
+    let before = database.seat_reservations.clone();
+
+    assert_eq!(
+      database.rebook_ticket(form(1, 0, 0), &mut provider),
+      Err(Error::Reservation(reserve_seat::Error::SeatAlreadyReserved))
+    );
+    provider.fail_next(Failure::Declined);
+    assert_eq!(
+      database.rebook_ticket(form(1, 1, 0), &mut provider),
+      Err(Error::Payment(process_payment::Error::Provider(
+        Failure::Declined
+      )))
+    );
+    assert_eq!(database.seat_reservations, before);
+    assert_eq!(database.payments[0].refunded.amount, 0);
+  }
+}
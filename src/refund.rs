@@ -0,0 +1,19 @@
+use crate::{money::Money, order, passenger, payment, time::Timestamp, trip};
+
+/// Record of a cancelled ticket and what was paid back for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Refund {
+  pub trip_id: trip::Id,
+  pub seat: usize,
+  pub from: usize,
+  pub to: usize,
+  pub order_id: Option<order::Id>,
+  pub passenger_id: Option<passenger::Id>,
+  /// The payments the amount was returned through, newest first. Empty when
+  /// nothing was returned or the order had no captured payment.
+  pub payment_ids: Vec<payment::Id>,
+  /// Actually paid back, zero when nothing went through a payment.
+  pub amount: Money,
+  pub fee: Money,
+  pub cancelled_at: Timestamp,
+}
@@ -1,25 +1,55 @@
 use {
   crate::{
+    bool_matrix::BoolMatrix,
     bus::Bus,
     bus_model::BusModel,
+    fare_type,
+    money::Currency,
+    order, passenger,
+    payment::Status,
+    place_order::PlaceOrder,
+    promo_code,
+    quote_fare::{self, QuoteFare},
     route::{self, Route},
+    seat_layout::PassengerAttribute,
     seat_reservation::{SeatReservation, SeatReservations},
     show_bus::ShowBus,
     show_bus_model::ShowBusModel,
+    show_passenger::ShowPassenger,
     show_route::ShowRoute,
+    show_station::ShowStation,
     show_trip::ShowTrip,
-    trip::{self, Trip},
+    stop,
+    time::Timestamp,
+    trip::{self, Trip, Vehicle},
     Database,
   },
-  std::{error, fmt::Display},
+  std::{error, fmt::Display, time::Duration},
 };
 
 #[derive(Clone, Default)]
 pub struct Form {
   pub seat_index: usize,
   pub from_stop_index: usize,
+  /// Where the passenger gets off, after `from_stop_index`. The seat is free
+  /// again from there on.
   pub to_stop_index: usize,
   pub trip_id: trip::Id,
+  pub requested_at: Timestamp,
+  /// Needs of an anonymous passenger; a linked passenger's own are used
+  /// instead.
+  pub passenger_attributes: Vec<PassengerAttribute>,
+  /// Reduced fare the passenger is entitled to, if any.
+  pub fare_type_id: Option<fare_type::Id>,
+  pub promo_code: Option<String>,
+  /// Who travels on the seat.
+  pub passenger_id: Option<passenger::Id>,
+  /// The purchase the seat is bought in, whose total grows by the seat. It
+  /// must be open and not paid for yet; seats are added to a paid order
+  /// through `modify_order`.
+  pub order_id: Option<order::Id>,
+  /// Currency to pay in, the route's own when unset.
+  pub currency: Option<Currency>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -32,64 +62,266 @@ pub enum Error {
   InvalidToStop,
   InvalidSeat,
   SeatAlreadyReserved,
+  TripDeparted,
+  InvalidClass,
+  NoSeatAvailable,
+  SeatRestricted(PassengerAttribute),
+  SeatBlocked,
+  /// Every seat block id has been handed out.
+  BlockIdsExhausted,
+  StopNotOnRoute,
+  FareNotFound,
+  FareTypeNotFound,
+  InvalidPromoCode(promo_code::Error),
+  CurrencyNotOffered(Currency),
+  CurrencyMismatch,
+  PriceOutOfRange,
+  PassengerNotFound,
+  OrderNotFound,
+  OrderCancelled,
+  /// The order has a payment under way or taken.
+  OrderPaid,
 }
 
 impl Display for Error {
-  fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    todo!()
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TripNotFound => write!(f, "trip not found"),
+      Self::RouteNotFound => write!(f, "route not found"),
+      Self::BusNotFound => write!(f, "bus not found"),
+      Self::BusModelNotFound => write!(f, "bus model not found"),
+      Self::InvalidFromStop => write!(f, "boarding stop is not on the trip"),
+      Self::InvalidToStop => write!(f, "alighting stop is not on the trip"),
+      Self::InvalidSeat => write!(f, "seat is not on the bus"),
+      Self::SeatAlreadyReserved => write!(f, "seat is already reserved"),
+      Self::TripDeparted => write!(f, "trip has departed"),
+      Self::InvalidClass => write!(f, "seat class not found on the bus"),
+      Self::NoSeatAvailable => write!(f, "no seat of the class is available"),
+      Self::SeatRestricted(attribute) => write!(f, "seat is kept for {attribute:?} passengers"),
+      Self::SeatBlocked => write!(f, "seat is blocked"),
+      Self::BlockIdsExhausted => write!(f, "no seat block id is left"),
+      Self::StopNotOnRoute => write!(f, "stop is not on the route"),
+      Self::FareNotFound => write!(f, "route has no fare for these stops"),
+      Self::FareTypeNotFound => write!(f, "fare type not found"),
+      Self::InvalidPromoCode(error) => write!(f, "{error}"),
+      Self::CurrencyNotOffered(currency) => write!(f, "fares are not offered in {currency}"),
+      Self::CurrencyMismatch => write!(f, "amounts are in different currencies"),
+      Self::PriceOutOfRange => write!(f, "price is out of range"),
+      Self::PassengerNotFound => write!(f, "passenger not found"),
+      Self::OrderNotFound => write!(f, "order not found"),
+      Self::OrderCancelled => write!(f, "order is cancelled"),
+      Self::OrderPaid => write!(f, "order is paid, modify it instead"),
+    }
   }
 }
 impl error::Error for Error {}
 
 pub struct Aggregate<'a> {
+  trip: &'a Trip,
+  route: &'a Route,
   route_stops: Vec<&'a route::Stop>,
   bus_model: &'a BusModel,
 }
 
 impl<'a> Aggregate<'a> {
-  fn stops_count(&self) -> usize {
+  /// The same trip run by another bus model, e.g. to plan a bus swap.
+  pub fn with_bus_model(self, bus_model: &'a BusModel) -> Self {
+    Self { bus_model, ..self }
+  }
+
+  pub const fn stops_count(&self) -> usize {
     self.route_stops.len()
   }
 
-  const fn seats_count(&self) -> usize {
-    self.bus_model.number_of_seats as usize
+  pub const fn bus_model(&self) -> &BusModel {
+    self.bus_model
+  }
+
+  pub const fn route(&self) -> &Route {
+    self.route
+  }
+
+  pub fn seats_count(&self) -> usize {
+    self.bus_model.seats_count()
+  }
+
+  /// When the bus leaves the stop, `None` when the route has no such stop.
+  pub fn departure_from(&self, stop_index: usize) -> Option<Timestamp> {
+    let stop = self.route_stops.get(stop_index)?;
+    Some(self.trip.departure.add(stop.departure_offset))
+  }
+
+  /// What a passenger with `attributes` boarding at the stop lacks to take
+  /// the seat at `at`, `None` when the seat is unrestricted or already
+  /// released to everyone. Release counts from the departure at that stop.
+  pub fn unmet_restriction(
+    &self,
+    seat_index: usize,
+    stop_index: usize,
+    attributes: &[PassengerAttribute],
+    at: Timestamp,
+  ) -> Option<PassengerAttribute> {
+    let restriction = self.bus_model.seat(seat_index)?.restriction?;
+    let released = restriction.release_before.is_some_and(|before| {
+      self
+        .departure_from(stop_index)
+        .is_some_and(|departure| at >= departure.sub(before))
+    });
+    (!released && !attributes.contains(&restriction.requires)).then_some(restriction.requires)
+  }
+
+  /// Time left at `at` before the bus leaves the stop, `None` once it has
+  /// or when the route has no such stop.
+  pub fn until_departure(&self, stop_index: usize, at: Timestamp) -> Option<Duration> {
+    let left = self.departure_from(stop_index)?.0.checked_sub(at.0)?;
+    u64::try_from(left).ok().map(Duration::from_secs)
   }
 }
 
 pub trait ReserveSeat {
   fn reserve_seat(&mut self, form: Form) -> Result<(), Error>;
 
-  fn build_aggregate(&self, trip_id: trip::Id) -> Result<Aggregate, Error>;
+  /// Runs every check `reserve_seat` does and returns the reservation it
+  /// would store, without storing it.
+  fn check_form(&self, form: &Form) -> Result<SeatReservation, Error>;
+
+  /// Gives back the promo code use held by a reservation being released.
+  fn release_promo_code(&mut self, reservation: &SeatReservation);
+
+  fn build_aggregate(&self, trip_id: trip::Id) -> Result<Aggregate<'_>, Error>;
+
+  /// Every reservation of the trip merged into a single seats × stops matrix.
+  fn build_occupancy(&self, trip_id: trip::Id, aggr: &Aggregate)
+    -> Result<SeatReservations, Error>;
+
+  /// Staff blocks of the trip as a layer of the same shape as the occupancy.
+  fn build_blocks(&self, trip_id: trip::Id, aggr: &Aggregate) -> BoolMatrix;
+
+  /// Position of a stop along the trip's route, for use as a stop index. A
+  /// station matches the route stop at any of its platforms.
+  #[allow(dead_code)]
+  fn find_stop_index(&self, trip_id: trip::Id, stop_id: stop::Id) -> Result<usize, Error>;
 }
 
 impl ReserveSeat for Database {
-  fn build_aggregate(&self, trip_id: trip::Id) -> Result<Aggregate, Error> {
+  fn build_aggregate(&self, trip_id: trip::Id) -> Result<Aggregate<'_>, Error> {
     let trip: &Trip = self.show_trip(trip_id).ok_or(Error::TripNotFound)?;
     let route: &Route = self.show_route(trip.route_id).ok_or(Error::RouteNotFound)?;
-    let bus: &Bus = self.show_bus(trip.bus_id).ok_or(Error::BusNotFound)?;
+    let bus_model_id = match trip.vehicle {
+      Vehicle::Bus(bus_id) => {
+        let bus: &Bus = self.show_bus(bus_id).ok_or(Error::BusNotFound)?;
+        bus.bus_model_id
+      }
+      Vehicle::BusModel(bus_model_id) => bus_model_id,
+    };
     let bus_model: &BusModel = self
-      .show_bus_model(bus.bus_model_id)
+      .show_bus_model(bus_model_id)
       .ok_or(Error::BusModelNotFound)?;
-    let route_stops: Vec<&route::Stop> = self
+    let mut route_stops: Vec<&route::Stop> = self
       .route_stops
       .iter()
       .filter(|rs| rs.route_id == route.id)
       .collect();
+    route_stops.sort_by_key(|rs| rs.index);
 
     Ok(Aggregate {
+      trip,
+      route,
       route_stops,
       bus_model,
     })
   }
 
+  fn build_occupancy(
+    &self,
+    trip_id: trip::Id,
+    aggr: &Aggregate,
+  ) -> Result<SeatReservations, Error> {
+    self
+      .seat_reservations
+      .iter()
+      .filter(|sr| sr.trip_id() == trip_id)
+      .try_fold(
+        SeatReservations::new(trip_id, aggr.seats_count(), aggr.stops_count()),
+        |acc, e| acc.try_add(e),
+      )
+      .ok_or(Error::SeatAlreadyReserved)
+  }
+
+  fn build_blocks(&self, trip_id: trip::Id, aggr: &Aggregate) -> BoolMatrix {
+    let mut layer = BoolMatrix::new(aggr.stops_count(), aggr.seats_count());
+    for block in self.seat_blocks.iter().filter(|b| b.trip_id == trip_id) {
+      block.apply(&mut layer);
+    }
+    layer
+  }
+
+  fn find_stop_index(&self, trip_id: trip::Id, stop_id: stop::Id) -> Result<usize, Error> {
+    let aggr = self.build_aggregate(trip_id)?;
+    let station = self.station_of(stop_id);
+
+    aggr
+      .route_stops
+      .iter()
+      .position(|rs| rs.stop_id == stop_id || self.station_of(rs.stop_id) == station)
+      .ok_or(Error::StopNotOnRoute)
+  }
+
   fn reserve_seat(&mut self, form: Form) -> Result<(), Error> {
+    let mut seat_reservation: SeatReservation = self.check_form(&form)?;
+
+    let aggr = self.build_aggregate(form.trip_id)?;
+    if aggr.route().fare.is_some() {
+      let quote = self.price(&form, &aggr).map_err(|error| match error {
+        quote_fare::Error::Reservation(error) => error,
+        quote_fare::Error::FareNotFound => Error::FareNotFound,
+        quote_fare::Error::FareTypeNotFound => Error::FareTypeNotFound,
+        quote_fare::Error::PromoCode(error) => Error::InvalidPromoCode(error),
+        quote_fare::Error::CurrencyNotOffered(currency) => Error::CurrencyNotOffered(currency),
+        quote_fare::Error::CurrencyMismatch => Error::CurrencyMismatch,
+        quote_fare::Error::OutOfRange => Error::PriceOutOfRange,
+      })?;
+      seat_reservation.set_quote(quote);
+    }
+    let order_total = form
+      .order_id
+      .map(|order_id| {
+        let total = self
+          .order_total(order_id)
+          .map_err(|_| Error::CurrencyMismatch)?;
+        match (total, seat_reservation.quote().map(|q| q.total)) {
+          (Some(total), Some(fare)) => total
+            .checked_add(fare)
+            .map(Some)
+            .ok_or(Error::CurrencyMismatch),
+          (total, fare) => Ok(total.or(fare)),
+        }
+      })
+      .transpose()?;
+    if let Some(code) = seat_reservation.promo_code() {
+      if let Some(promo_code) = self.promo_codes.iter_mut().find(|pc| pc.matches(code)) {
+        promo_code.uses += 1;
+      }
+    }
+
+    self.seat_reservations.push(seat_reservation);
+    if let (Some(order_id), Some(total)) = (form.order_id, order_total) {
+      if let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) {
+        order.total = total;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn check_form(&self, form: &Form) -> Result<SeatReservation, Error> {
     let aggr = self.build_aggregate(form.trip_id)?;
 
     if form.from_stop_index >= aggr.stops_count() {
       return Err(Error::InvalidFromStop);
     }
 
-    if form.to_stop_index >= aggr.stops_count() {
+    if form.to_stop_index >= aggr.stops_count() || form.to_stop_index <= form.from_stop_index {
       return Err(Error::InvalidToStop);
     }
 
@@ -97,7 +329,48 @@ impl ReserveSeat for Database {
       return Err(Error::InvalidSeat);
     }
 
-    let seat_reservation: SeatReservation = SeatReservation::new(
+    if aggr
+      .until_departure(form.from_stop_index, form.requested_at)
+      .is_none()
+    {
+      return Err(Error::TripDeparted);
+    }
+
+    let passenger_attributes = match form.passenger_id {
+      Some(passenger_id) => {
+        &self
+          .show_passenger(passenger_id)
+          .ok_or(Error::PassengerNotFound)?
+          .attributes
+      }
+      None => &form.passenger_attributes,
+    };
+    if let Some(requires) = aggr.unmet_restriction(
+      form.seat_index,
+      form.from_stop_index,
+      passenger_attributes,
+      form.requested_at,
+    ) {
+      return Err(Error::SeatRestricted(requires));
+    }
+
+    if let Some(fare_type_id) = form.fare_type_id {
+      if !self.fare_types.iter().any(|ft| ft.id == fare_type_id) {
+        return Err(Error::FareTypeNotFound);
+      }
+    }
+
+    if let Some(code) = &form.promo_code {
+      self
+        .promo_codes
+        .iter()
+        .find(|pc| pc.matches(code))
+        .ok_or(Error::InvalidPromoCode(promo_code::Error::NotFound))?
+        .check(aggr.route.id, form.requested_at)
+        .map_err(Error::InvalidPromoCode)?;
+    }
+
+    let mut seat_reservation: SeatReservation = SeatReservation::new(
       form.trip_id,
       form.seat_index,
       form.from_stop_index,
@@ -105,23 +378,53 @@ impl ReserveSeat for Database {
       aggr.seats_count(),
       aggr.stops_count(),
     );
+    if let Some(order_id) = form.order_id {
+      let order = self
+        .orders
+        .iter()
+        .find(|o| o.id == order_id)
+        .ok_or(Error::OrderNotFound)?;
+      if order.status == order::Status::Cancelled {
+        return Err(Error::OrderCancelled);
+      }
+      if self
+        .payments
+        .iter()
+        .any(|p| p.order_id == order_id && !matches!(p.status, Status::Failed(_) | Status::Voided))
+      {
+        return Err(Error::OrderPaid);
+      }
+    }
+    seat_reservation.set_passenger_id(form.passenger_id);
+    seat_reservation.set_order_id(form.order_id);
+    seat_reservation.set_promo_code(form.promo_code.clone());
 
-    let seat_reservations: SeatReservations = self
-      .seat_reservations
-      .iter()
-      .filter(|sr| sr.trip_id() == form.trip_id)
-      .try_fold(
-        SeatReservations::new(form.trip_id, aggr.seats_count(), aggr.stops_count()),
-        |acc, e| acc.try_add(e),
-      ).unwrap();
+    let seat_reservations: SeatReservations = self.build_occupancy(form.trip_id, &aggr)?;
 
-    if seat_reservations.matrix.has_colliding_bits(&seat_reservation.matrix) {
+    if seat_reservations
+      .matrix
+      .has_colliding_bits(&seat_reservation.matrix)
+    {
       return Err(Error::SeatAlreadyReserved);
     }
 
-    self.seat_reservations.push(seat_reservation);
+    if self
+      .build_blocks(form.trip_id, &aggr)
+      .has_colliding_bits(&seat_reservation.matrix)
+    {
+      return Err(Error::SeatBlocked);
+    }
 
-    Ok(())
+    Ok(seat_reservation)
+  }
+
+  fn release_promo_code(&mut self, reservation: &SeatReservation) {
+    let Some(code) = reservation.promo_code() else {
+      return;
+    };
+    if let Some(promo_code) = self.promo_codes.iter_mut().find(|pc| pc.matches(code)) {
+      promo_code.uses = promo_code.uses.saturating_sub(1);
+    }
   }
 }
 
@@ -129,7 +432,16 @@ impl ReserveSeat for Database {
 mod tests {
   use {
     super::*,
-    crate::{bus, bus_model},
+    crate::{
+      bus, bus_model,
+      fixtures::{database, item, order_database, stops},
+      money::Money,
+      order::Order,
+      passenger::Passenger,
+      payment,
+      process_payment::{self, ProcessPayment},
+      seat_layout::{Restriction, Seat, SeatLayout},
+    },
   };
 
   #[test]
@@ -140,10 +452,15 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
     assert_eq!(result, Err(Error::TripNotFound));
+    assert_eq!(
+      result.map_err(|error| error.to_string()),
+      Err("trip not found".to_string())
+    );
   }
 
   #[test]
@@ -160,6 +477,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
@@ -174,7 +492,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       ..Database::default()
     };
     let form = Form {
@@ -182,6 +503,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
@@ -196,7 +518,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       buses: vec![Bus {
         id: bus::Id(0),
         bus_model_id: bus_model::Id(0),
@@ -209,6 +534,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
 
     let result = database.reserve_seat(form);
@@ -224,7 +550,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       buses: vec![Bus {
         id: bus::Id(0),
         bus_model_id: bus_model::Id(0),
@@ -241,6 +570,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
@@ -255,7 +585,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       route_stops: vec![route::Stop {
         route_id: route::Id(0),
         ..Default::default()
@@ -276,6 +609,53 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
+    };
+    let result = database.reserve_seat(form);
+
+    assert_eq!(result, Err(Error::InvalidToStop));
+  }
+
+  #[test]
+  fn to_stop_before_from_stop() {
+    let mut database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        ..Default::default()
+      }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: vec![
+        route::Stop {
+          route_id: route::Id(0),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          ..Default::default()
+        },
+      ],
+      buses: vec![Bus {
+        id: bus::Id(0),
+        bus_model_id: bus_model::Id(0),
+      }],
+      bus_models: vec![BusModel {
+        id: bus_model::Id(0),
+        number_of_seats: 1,
+        ..Default::default()
+      }],
+      ..Database::default()
+    };
+
+    let form = Form {
+      seat_index: 0,
+      from_stop_index: 1,
+      to_stop_index: 0,
+      trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
@@ -290,7 +670,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       route_stops: vec![
         route::Stop {
           route_id: route::Id(0),
@@ -317,6 +700,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
     let result = database.reserve_seat(form);
 
@@ -331,7 +715,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       route_stops: vec![
         route::Stop {
           route_id: route::Id(0),
@@ -359,6 +746,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
 
     let result = database.reserve_seat(form);
@@ -366,6 +754,253 @@ mod tests {
     assert_eq!(result, Ok(()));
   }
 
+  #[test]
+  fn trip_departed() {
+    let mut database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        departure: Timestamp(1_000),
+        ..Default::default()
+      }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: vec![
+        route::Stop {
+          route_id: route::Id(0),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          index: 1,
+          departure_offset: std::time::Duration::from_mins(10),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          index: 2,
+          departure_offset: std::time::Duration::from_mins(20),
+          ..Default::default()
+        },
+      ],
+      buses: vec![Bus {
+        id: bus::Id(0),
+        bus_model_id: bus_model::Id(0),
+      }],
+      bus_models: vec![BusModel {
+        id: bus_model::Id(0),
+        number_of_seats: 1,
+        ..Default::default()
+      }],
+      ..Database::default()
+    };
+
+    let form = Form {
+      seat_index: 0,
+      from_stop_index: 0,
+      to_stop_index: 1,
+      trip_id: trip::Id(0),
+      requested_at: Timestamp(1_001),
+      ..Default::default()
+    };
+    let boarding_later = Form {
+      from_stop_index: 1,
+      to_stop_index: 2,
+      ..form.clone()
+    };
+
+    assert_eq!(database.reserve_seat(form), Err(Error::TripDeparted));
+    assert_eq!(database.reserve_seat(boarding_later), Ok(()));
+    let until = |stop_index: usize, at: i64| {
+      database
+        .build_aggregate(trip::Id(0))
+        .map(|aggr| aggr.until_departure(stop_index, Timestamp(at)))
+    };
+    assert_eq!(until(3, 0), Ok(None));
+    assert_eq!(until(0, i64::MIN), Ok(None));
+    assert_eq!(until(0, 0), Ok(Some(std::time::Duration::from_secs(1_000))));
+  }
+
+  #[test]
+  fn restricted_seat() {
+    let mut database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        departure: Timestamp(36_000),
+        ..Default::default()
+      }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: vec![
+        route::Stop {
+          route_id: route::Id(0),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          index: 1,
+          departure_offset: std::time::Duration::from_hours(3),
+          ..Default::default()
+        },
+        route::Stop {
+          route_id: route::Id(0),
+          index: 2,
+          ..Default::default()
+        },
+      ],
+      passengers: vec![Passenger {
+        id: passenger::Id(0),
+        attributes: vec![PassengerAttribute::Wheelchair],
+        ..Default::default()
+      }],
+      buses: vec![Bus {
+        id: bus::Id(0),
+        bus_model_id: bus_model::Id(0),
+      }],
+      bus_models: vec![BusModel {
+        id: bus_model::Id(0),
+        number_of_seats: 1,
+        layout: Some(SeatLayout {
+          decks: 1,
+          seats: vec![Seat {
+            restriction: Some(Restriction {
+              requires: PassengerAttribute::Wheelchair,
+              release_before: Some(std::time::Duration::from_hours(2)),
+            }),
+            ..Default::default()
+          }],
+          ..Default::default()
+        }),
+        ..Default::default()
+      }],
+      ..Database::default()
+    };
+
+    let form = Form {
+      seat_index: 0,
+      from_stop_index: 0,
+      to_stop_index: 1,
+      trip_id: trip::Id(0),
+      requested_at: Timestamp(0),
+      passenger_attributes: vec![PassengerAttribute::Priority],
+      ..Default::default()
+    };
+    let eligible = Form {
+      passenger_attributes: vec![PassengerAttribute::Wheelchair],
+      ..form.clone()
+    };
+    let released = Form {
+      requested_at: Timestamp(28_800),
+      ..form.clone()
+    };
+    let boarding_later = Form {
+      from_stop_index: 1,
+      to_stop_index: 2,
+      ..released.clone()
+    };
+    let linked = Form {
+      passenger_id: Some(passenger::Id(0)),
+      ..form.clone()
+    };
+
+    assert_eq!(
+      database.reserve_seat(form),
+      Err(Error::SeatRestricted(PassengerAttribute::Wheelchair))
+    );
+    assert_eq!(database.reserve_seat(eligible), Ok(()));
+    database.seat_reservations.clear();
+    assert_eq!(database.reserve_seat(released), Ok(()));
+    database.seat_reservations.clear();
+    assert_eq!(
+      database.reserve_seat(boarding_later),
+      Err(Error::SeatRestricted(PassengerAttribute::Wheelchair))
+    );
+    assert_eq!(database.reserve_seat(linked), Ok(()));
+  }
+
+  #[test]
+  fn large_bus_and_route() {
+    let mut database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        ..Default::default()
+      }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: (0..320)
+        .map(|index| route::Stop {
+          route_id: route::Id(0),
+          index,
+          ..Default::default()
+        })
+        .collect(),
+      buses: vec![Bus {
+        id: bus::Id(0),
+        bus_model_id: bus_model::Id(0),
+      }],
+      bus_models: vec![BusModel {
+        id: bus_model::Id(0),
+        number_of_seats: 300,
+        ..Default::default()
+      }],
+      ..Database::default()
+    };
+
+    let form = Form {
+      seat_index: 299,
+      from_stop_index: 0,
+      to_stop_index: 300,
+      trip_id: trip::Id(0),
+      ..Default::default()
+    };
+    let next_leg = Form {
+      from_stop_index: 300,
+      to_stop_index: 319,
+      ..form.clone()
+    };
+    let overlapping = Form {
+      from_stop_index: 299,
+      ..form.clone()
+    };
+    let out_of_range = Form {
+      seat_index: 300,
+      ..form.clone()
+    };
+
+    assert_eq!(database.reserve_seat(form), Ok(()));
+    assert_eq!(database.reserve_seat(next_leg), Ok(()));
+    assert_eq!(
+      database.reserve_seat(overlapping),
+      Err(Error::SeatAlreadyReserved)
+    );
+    assert_eq!(database.reserve_seat(out_of_range), Err(Error::InvalidSeat));
+    assert_eq!(database.seat_reservations[0].matrix.data().len(), 12_000);
+  }
+
+  #[test]
+  fn find_stop_index_by_station() {
+    let mut database = database();
+    database.stops = stops();
+    database.route_stops[0].stop_id = stop::Id(3);
+    database.route_stops[1].stop_id = stop::Id(2);
+
+    assert_eq!(database.find_stop_index(trip::Id(0), stop::Id(3)), Ok(0));
+    assert_eq!(database.find_stop_index(trip::Id(0), stop::Id(0)), Ok(1));
+    assert_eq!(database.find_stop_index(trip::Id(0), stop::Id(1)), Ok(1));
+    assert_eq!(
+      database.find_stop_index(trip::Id(0), stop::Id(9)),
+      Err(Error::StopNotOnRoute)
+    );
+  }
+
   #[test]
   fn already_reserved() {
     let mut database: Database = Database {
@@ -374,7 +1009,10 @@ mod tests {
         route_id: route::Id(0),
         ..Default::default()
       }],
-      routes: vec![Route { id: route::Id(0) }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
       route_stops: vec![
         route::Stop {
           route_id: route::Id(0),
@@ -402,6 +1040,7 @@ mod tests {
       from_stop_index: 0,
       to_stop_index: 1,
       trip_id: trip::Id(0),
+      ..Default::default()
     };
 
     let result = database.reserve_seat(form.clone());
@@ -410,4 +1049,33 @@ mod tests {
     assert_eq!(result, Ok(()));
     assert_eq!(second_result, Err(Error::SeatAlreadyReserved));
   }
+
+  #[test]
+  fn joins_open_unpaid_orders() {
+    let mut database = order_database();
+    database.orders.push(Order::default());
+    let join = |trip_id, seat_index| Form {
+      order_id: Some(order::Id(0)),
+      ..item(trip_id, seat_index)
+    };
+
+    assert_eq!(database.reserve_seat(join(0, 0)), Ok(()));
+    assert_eq!(database.reserve_seat(join(1, 0)), Ok(()));
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(2_000, Currency::XXX))
+    );
+
+    let _ = database.create_payment(process_payment::Form {
+      id: payment::Id(0),
+      order_id: order::Id(0),
+    });
+    assert_eq!(database.reserve_seat(join(0, 1)), Err(Error::OrderPaid));
+    database.orders[0].status = order::Status::Cancelled;
+    assert_eq!(
+      database.reserve_seat(join(0, 1)),
+      Err(Error::OrderCancelled)
+    );
+    assert_eq!(database.seat_reservations.len(), 2);
+  }
 }
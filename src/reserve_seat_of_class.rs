@@ -0,0 +1,137 @@
+use crate::{
+  fare_type,
+  money::Currency,
+  order, passenger,
+  reserve_seat::{self, Error, ReserveSeat},
+  seat_layout::{ClassId, PassengerAttribute},
+  time::Timestamp,
+  trip, Database,
+};
+
+#[derive(Clone, Default)]
+pub struct Form {
+  pub passenger_attributes: Vec<PassengerAttribute>,
+  pub class_id: ClassId,
+  pub from_stop_index: usize,
+  pub to_stop_index: usize,
+  pub trip_id: trip::Id,
+  pub requested_at: Timestamp,
+  pub fare_type_id: Option<fare_type::Id>,
+  pub promo_code: Option<String>,
+  pub passenger_id: Option<passenger::Id>,
+  pub order_id: Option<order::Id>,
+  pub currency: Option<Currency>,
+}
+
+#[allow(dead_code)]
+pub trait ReserveSeatOfClass {
+  /// Reserves the first seat of the class that is free between the stops and
+  /// that the passenger is eligible for, returning its index.
+  fn reserve_seat_of_class(&mut self, form: Form) -> Result<usize, Error>;
+}
+
+impl ReserveSeatOfClass for Database {
+  fn reserve_seat_of_class(&mut self, form: Form) -> Result<usize, Error> {
+    let aggr = self.build_aggregate(form.trip_id)?;
+    let layout = aggr
+      .bus_model()
+      .layout
+      .as_ref()
+      .ok_or(Error::InvalidClass)?;
+    if !layout.classes.iter().any(|c| c.id == form.class_id) {
+      return Err(Error::InvalidClass);
+    }
+
+    for seat_index in layout.seat_indices_of_class(form.class_id) {
+      let result = self.reserve_seat(reserve_seat::Form {
+        seat_index,
+        from_stop_index: form.from_stop_index,
+        to_stop_index: form.to_stop_index,
+        trip_id: form.trip_id,
+        requested_at: form.requested_at,
+        passenger_attributes: form.passenger_attributes.clone(),
+        fare_type_id: form.fare_type_id,
+        promo_code: form.promo_code.clone(),
+        passenger_id: form.passenger_id,
+        order_id: form.order_id,
+        currency: form.currency,
+      });
+      match result {
+        Ok(()) => return Ok(seat_index),
+        Err(Error::SeatAlreadyReserved | Error::SeatRestricted(_) | Error::SeatBlocked) => {}
+        Err(error) => return Err(error),
+      }
+    }
+
+    Err(Error::NoSeatAvailable)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      fixtures::{class_database, CAMA},
+      seat_block::{self, Reason, SeatBlock},
+    },
+  };
+
+  #[test]
+  fn invalid_class() {
+    let mut database = class_database();
+    let form = Form {
+      class_id: ClassId(9),
+      to_stop_index: 1,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.reserve_seat_of_class(form),
+      Err(Error::InvalidClass)
+    );
+  }
+
+  #[test]
+  fn fills_class_then_runs_out() {
+    let mut database = class_database();
+    let form = Form {
+      class_id: CAMA,
+      to_stop_index: 1,
+      ..Default::default()
+    };
+    let later_segment = Form {
+      from_stop_index: 1,
+      to_stop_index: 2,
+      ..form.clone()
+    };
+
+    assert_eq!(database.reserve_seat_of_class(form.clone()), Ok(0));
+    assert_eq!(database.reserve_seat_of_class(form.clone()), Ok(1));
+    assert_eq!(
+      database.reserve_seat_of_class(form),
+      Err(Error::NoSeatAvailable)
+    );
+    assert_eq!(database.reserve_seat_of_class(later_segment), Ok(0));
+  }
+
+  #[test]
+  fn skips_blocked_seats() {
+    let mut database = class_database();
+    database.seat_blocks.push(SeatBlock {
+      id: seat_block::Id(0),
+      trip_id: trip::Id(0),
+      seat: 0,
+      from: 0,
+      to: 1,
+      reason: Reason::CrewRest,
+    });
+    let form = Form {
+      class_id: CAMA,
+      to_stop_index: 1,
+      ..Default::default()
+    };
+
+    assert_eq!(database.reserve_seat_of_class(form), Ok(1));
+  }
+}
@@ -1,21 +1,81 @@
-use crate::stop;
+use {
+  crate::{
+    cancellation::{CancellationPolicy, ChangePolicy},
+    fare::{Fare, PricingPolicy},
+    money::{Currency, Exact, ExchangeRate, Money, Rounding, TaxRule},
+    stop,
+  },
+  std::time::Duration,
+};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Id(pub u64);
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[allow(dead_code)]
 pub struct Route {
   pub id: Id,
+  pub name: String,
+  /// Short public code, e.g. "501X".
+  pub code: String,
+  pub direction: Direction,
+  /// The route this one was derived from, e.g. the all-stops service an
+  /// express variant skips stops of.
+  pub base_id: Option<Id>,
+  pub fare: Option<Fare>,
+  /// Currency of `fare`. Other currencies are offered through
+  /// `exchange_rates`.
+  pub currency: Currency,
+  pub exchange_rates: Vec<ExchangeRate>,
+  pub pricing: PricingPolicy,
+  pub tax_rules: Vec<TaxRule>,
+  /// Applied to every computed amount of a quote on this route.
+  pub rounding: Rounding,
+  pub cancellation: CancellationPolicy,
+  pub change: ChangePolicy,
+}
+
+impl Route {
+  /// Brings an amount into `currency`: as is when already in it, through
+  /// the exchange rate when in the route's. `None` otherwise.
+  pub fn convert_to(&self, currency: Currency) -> impl Fn(Money) -> Option<Exact> + '_ {
+    let rate = self
+      .exchange_rates
+      .iter()
+      .find(|r| r.currency() == currency);
+    move |amount: Money| match rate {
+      _ if amount.currency == currency => Some(Exact::from(amount)),
+      Some(rate) if amount.currency == self.currency => rate.convert(amount.into()),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Direction {
+  #[default]
+  Outbound,
+  Inbound,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct StopId(pub u64);
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Stop {
   pub id: StopId,
   #[allow(clippy::struct_field_names)]
   pub stop_id: stop::Id,
   pub route_id: Id,
-  pub index: u8,
+  pub index: u16,
+  /// Offsets from the trip departure at the first stop.
+  pub arrival_offset: Duration,
+  pub departure_offset: Duration,
+  /// Length of the leg arriving at this stop from the previous one. Zero for
+  /// the first stop.
+  pub leg_distance_meters: u32,
+  /// Scheduled driving time of the leg arriving at this stop, which is the
+  /// gap between the previous stop's departure offset and this arrival.
+  pub leg_running_time: Duration,
 }
@@ -0,0 +1,34 @@
+use crate::{
+  route,
+  time::{Date, Time, TimeZone, Weekday},
+  trip::Vehicle,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+/// A recurring departure pattern that trips are materialised from.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Schedule {
+  pub id: Id,
+  pub route_id: route::Id,
+  /// Trips get the bus, or only its model when buses are assigned later.
+  pub vehicle: Vehicle,
+  /// Local time in `time_zone`, kept across daylight saving changes.
+  pub departure_time: Time,
+  pub time_zone: TimeZone,
+  pub days: Vec<Weekday>,
+  pub valid_from: Date,
+  pub valid_until: Date,
+  /// Dates within the validity range without service, e.g. holidays.
+  pub exceptions: Vec<Date>,
+}
+
+impl Schedule {
+  pub fn runs_on(&self, date: Date) -> bool {
+    self.valid_from <= date
+      && date <= self.valid_until
+      && self.days.contains(&date.weekday())
+      && !self.exceptions.contains(&date)
+  }
+}
@@ -0,0 +1,218 @@
+use crate::{show_station::ShowStation, show_stop::ShowStop, stop, Database};
+
+#[derive(Debug)]
+pub struct Match<'a> {
+  pub stop: &'a stop::Stop,
+  /// Lower is better.
+  pub score: u32,
+}
+
+#[allow(dead_code)]
+pub trait SearchStops {
+  /// Stops whose name or an alias matches every word of `query` by prefix,
+  /// abbreviation or a small typo, ignoring case and accents. Platforms are
+  /// reported as their station. Best matches come first.
+  fn search_stops(&self, query: &str, limit: usize) -> Vec<Match<'_>>;
+}
+
+impl SearchStops for Database {
+  fn search_stops(&self, query: &str, limit: usize) -> Vec<Match<'_>> {
+    let query = normalize(query);
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+      return vec![];
+    }
+
+    let mut matches: Vec<Match> = self
+      .stops
+      .iter()
+      .filter_map(|stop| {
+        let score = std::iter::once(&stop.name)
+          .chain(stop.aliases.iter())
+          .filter_map(|name| score_name(&query, &words, &normalize(name)))
+          .min()?;
+        let stop = self.show_stop(self.station_of(stop.id)).unwrap_or(stop);
+        Some(Match { stop, score })
+      })
+      .collect();
+    matches.sort_by(|a, b| {
+      a.score
+        .cmp(&b.score)
+        .then_with(|| a.stop.name.cmp(&b.stop.name))
+    });
+    let mut seen: Vec<stop::Id> = vec![];
+    matches.retain(|m| {
+      let first = !seen.contains(&m.stop.id);
+      seen.push(m.stop.id);
+      first
+    });
+    matches.truncate(limit);
+    matches
+  }
+}
+
+fn score_name(query: &str, words: &[&str], name: &str) -> Option<u32> {
+  if name == query {
+    return Some(0);
+  }
+  if name.starts_with(query) {
+    return Some(1);
+  }
+  let tokens: Vec<&str> = name.split_whitespace().collect();
+  words.iter().try_fold(2, |total, word| {
+    let best = tokens
+      .iter()
+      .filter_map(|token| score_word(word, token))
+      .min()?;
+    Some(total + best)
+  })
+}
+
+fn score_word(word: &str, token: &str) -> Option<u32> {
+  if token == word {
+    return Some(0);
+  }
+  if token.starts_with(word) {
+    return Some(1);
+  }
+  if is_abbreviation(word, token) {
+    return Some(3);
+  }
+  // Compare against the token prefix of the same length so that typos in a
+  // partially typed word still match.
+  let word: Vec<char> = word.chars().collect();
+  let token: Vec<char> = token.chars().collect();
+  let allowed = word.len() / 4;
+  (allowed > 0)
+    .then(|| {
+      (word.len().saturating_sub(1)..=(word.len() + 1).min(token.len()))
+        .map(|len| edit_distance(&word, &token[..len]))
+        .min()
+    })
+    .flatten()
+    .filter(|&distance| distance <= allowed)
+    .map(|distance| 4 + u32::try_from(distance).unwrap_or(u32::MAX - 4))
+}
+
+/// "stgo" for "santiago": same first letter and the rest in order.
+fn is_abbreviation(word: &str, token: &str) -> bool {
+  let mut token = token.chars();
+  word.len() > 1
+    && word.chars().next() == token.next()
+    && word.chars().skip(1).all(|c| token.any(|t| t == c))
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and transpositions of adjacent characters.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+  let mut rows: Vec<Vec<usize>> = (0..=a.len()).map(|i| vec![i; b.len() + 1]).collect();
+  for (j, cell) in rows[0].iter_mut().enumerate() {
+    *cell = j;
+  }
+  for i in 1..=a.len() {
+    for j in 1..=b.len() {
+      let cost = usize::from(a[i - 1] != b[j - 1]);
+      let mut best = (rows[i - 1][j] + 1)
+        .min(rows[i][j - 1] + 1)
+        .min(rows[i - 1][j - 1] + cost);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        best = best.min(rows[i - 2][j - 2] + 1);
+      }
+      rows[i][j] = best;
+    }
+  }
+  rows[a.len()][b.len()]
+}
+
+/// Lowercases, folds accents and turns punctuation into spaces.
+fn normalize(text: &str) -> String {
+  text
+    .to_lowercase()
+    .chars()
+    .map(|c| match c {
+      'á' | 'à' | 'â' | 'ä' | 'ã' => 'a',
+      'é' | 'è' | 'ê' | 'ë' => 'e',
+      'í' | 'ì' | 'î' | 'ï' => 'i',
+      'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+      'ú' | 'ù' | 'û' | 'ü' => 'u',
+      'ñ' => 'n',
+      'ç' => 'c',
+      c if c.is_alphanumeric() => c,
+      _ => ' ',
+    })
+    .collect::<String>()
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn database() -> Database {
+    let stop = |id: u64, name: &str, aliases: &[&str]| stop::Stop {
+      id: stop::Id(id),
+      name: name.to_string(),
+      aliases: aliases.iter().map(ToString::to_string).collect(),
+      ..Default::default()
+    };
+    Database {
+      stops: vec![
+        stop(0, "Terminal Alameda", &["Santiago Terminal"]),
+        stop(1, "Concepción", &[]),
+        stop(2, "Santa Cruz", &[]),
+        stop(3, "Santiago", &[]),
+        stop(4, "Valparaíso", &["Valpo"]),
+      ],
+      ..Default::default()
+    }
+  }
+
+  fn ids(matches: &[Match]) -> Vec<u64> {
+    matches.iter().map(|m| m.stop.id.0).collect()
+  }
+
+  #[test]
+  fn prefix() {
+    assert_eq!(ids(&database().search_stops("san", 10)), vec![2, 3, 0]);
+  }
+
+  #[test]
+  fn abbreviation() {
+    assert_eq!(ids(&database().search_stops("stgo", 10)), vec![3, 0]);
+  }
+
+  #[test]
+  fn accents_and_aliases() {
+    assert_eq!(ids(&database().search_stops("CONCEPCION", 10)), vec![1]);
+    assert_eq!(
+      ids(&database().search_stops("santiago terminal", 10)),
+      vec![0]
+    );
+    assert_eq!(ids(&database().search_stops("valpo", 10)), vec![4]);
+  }
+
+  #[test]
+  fn typos() {
+    assert_eq!(ids(&database().search_stops("Valparisoo", 10)), vec![4]);
+    assert_eq!(ids(&database().search_stops("conecpcion", 10)), vec![1]);
+  }
+
+  #[test]
+  fn platforms_as_station() {
+    let database: Database = Database {
+      stops: crate::fixtures::stops(),
+      ..Default::default()
+    };
+
+    assert_eq!(ids(&database.search_stops("terminal sur", 10)), vec![0]);
+    assert_eq!(ids(&database.search_stops("anden 2", 10)), vec![0]);
+  }
+
+  #[test]
+  fn limit_and_empty_query() {
+    assert_eq!(database().search_stops("san", 1).len(), 1);
+    assert!(database().search_stops("  ", 10).is_empty());
+  }
+}
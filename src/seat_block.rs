@@ -0,0 +1,36 @@
+use crate::{bool_matrix::BoolMatrix, trip};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Id(pub u64);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Reason {
+  CrewRest,
+  OutOfService,
+  TourGuide,
+  Other(String),
+}
+
+/// A seat held back by staff over a stop range, kept apart from passenger
+/// reservations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeatBlock {
+  pub id: Id,
+  pub trip_id: trip::Id,
+  pub seat: usize,
+  /// Held on the legs leaving `from..to`, like a reservation.
+  pub from: usize,
+  pub to: usize,
+  pub reason: Reason,
+}
+
+impl SeatBlock {
+  /// Marks the block on `layer`, ignoring seats or stops the layer lacks.
+  pub fn apply(&self, layer: &mut BoolMatrix) {
+    let fits = self.seat < layer.height && self.to < layer.width;
+    if fits && self.from < self.to {
+      layer.set_row_range(self.seat, self.from, self.to - 1, true);
+    }
+  }
+}
@@ -0,0 +1,267 @@
+use std::{error, fmt::Display, time::Duration};
+
+/// Physical arrangement of a bus model. Seat `i` of the layout is the seat
+/// reservations refer to with `seat_index == i`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SeatLayout {
+  pub decks: u8,
+  pub classes: Vec<Class>,
+  pub seats: Vec<Seat>,
+  pub fixtures: Vec<Fixture>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Seat {
+  /// Customer facing name, e.g. "12A".
+  pub label: String,
+  pub position: Position,
+  pub window: bool,
+  pub aisle: bool,
+  pub class_id: Option<ClassId>,
+  pub restriction: Option<Restriction>,
+}
+
+/// Who a seat is kept for, e.g. a wheelchair space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Restriction {
+  pub requires: PassengerAttribute,
+  /// How long before departure the seat is released to everyone, if ever.
+  pub release_before: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum PassengerAttribute {
+  Wheelchair,
+  Priority,
+  UnaccompaniedMinor,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ClassId(pub u64);
+
+/// A priced tier of seats, e.g. "cama" or "semi-cama".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Class {
+  pub id: ClassId,
+  pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Position {
+  pub deck: u8,
+  pub row: u8,
+  pub column: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum FixtureKind {
+  Door,
+  Stairs,
+  Driver,
+  Toilet,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fixture {
+  pub kind: FixtureKind,
+  pub position: Position,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  SeatCountMismatch { layout: usize, model: usize },
+  DuplicateLabel(String),
+  PositionTaken(Position),
+  DeckOutOfRange(Position),
+  UnknownClass(ClassId),
+  DuplicateClass(ClassId),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::SeatCountMismatch { layout, model } => {
+        write!(f, "layout has {layout} seats but the bus model has {model}")
+      }
+      Self::DuplicateLabel(label) => write!(f, "seat label {label} is used twice"),
+      Self::PositionTaken(position) => write!(
+        f,
+        "two seats at deck {} row {} column {}",
+        position.deck, position.row, position.column
+      ),
+      Self::DeckOutOfRange(position) => write!(f, "deck {} does not exist", position.deck),
+      Self::UnknownClass(id) => write!(f, "seat class {} not found", id.0),
+      Self::DuplicateClass(id) => write!(f, "seat class {} is defined twice", id.0),
+    }
+  }
+}
+impl error::Error for Error {}
+
+impl SeatLayout {
+  pub fn validate(&self, number_of_seats: usize) -> Result<(), Error> {
+    if self.seats.len() != number_of_seats {
+      return Err(Error::SeatCountMismatch {
+        layout: self.seats.len(),
+        model: number_of_seats,
+      });
+    }
+    let positions: Vec<Position> = self
+      .seats
+      .iter()
+      .map(|s| s.position)
+      .chain(self.fixtures.iter().map(|f| f.position))
+      .collect();
+    for (i, &position) in positions.iter().enumerate() {
+      if position.deck >= self.decks {
+        return Err(Error::DeckOutOfRange(position));
+      }
+      if positions[..i].contains(&position) {
+        return Err(Error::PositionTaken(position));
+      }
+    }
+    for (i, class) in self.classes.iter().enumerate() {
+      if self.classes[..i].iter().any(|c| c.id == class.id) {
+        return Err(Error::DuplicateClass(class.id));
+      }
+    }
+    for (i, seat) in self.seats.iter().enumerate() {
+      if self.seats[..i].iter().any(|s| s.label == seat.label) {
+        return Err(Error::DuplicateLabel(seat.label.clone()));
+      }
+      match seat.class_id {
+        Some(id) if !self.classes.iter().any(|c| c.id == id) => {
+          return Err(Error::UnknownClass(id));
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  pub fn seat(&self, index: usize) -> Option<&Seat> {
+    self.seats.get(index)
+  }
+
+  pub fn seat_index(&self, label: &str) -> Option<usize> {
+    self.seats.iter().position(|s| s.label == label)
+  }
+
+  pub fn seat_indices_of_class(&self, class_id: ClassId) -> Vec<usize> {
+    (0..self.seats.len())
+      .filter(|&i| self.seats[i].class_id == Some(class_id))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn layout() -> SeatLayout {
+    let seat = |label: &str, row: u8, column: u8| Seat {
+      label: label.to_string(),
+      position: Position {
+        deck: 0,
+        row,
+        column,
+      },
+      window: column == 0 || column == 3,
+      aisle: column == 1 || column == 2,
+      class_id: None,
+      restriction: None,
+    };
+    SeatLayout {
+      decks: 1,
+      classes: vec![],
+      seats: vec![seat("1A", 1, 0), seat("1B", 1, 1), seat("1C", 1, 3)],
+      fixtures: vec![Fixture {
+        kind: FixtureKind::Door,
+        position: Position {
+          deck: 0,
+          row: 0,
+          column: 3,
+        },
+      }],
+    }
+  }
+
+  #[test]
+  fn valid() {
+    assert_eq!(layout().validate(3), Ok(()));
+  }
+
+  #[test]
+  fn seat_count_mismatch() {
+    assert_eq!(
+      layout().validate(4),
+      Err(Error::SeatCountMismatch {
+        layout: 3,
+        model: 4
+      })
+    );
+  }
+
+  #[test]
+  fn duplicate_label() {
+    let mut layout = layout();
+    layout.seats[2].label = "1A".to_string();
+
+    assert_eq!(
+      layout.validate(3),
+      Err(Error::DuplicateLabel("1A".to_string()))
+    );
+  }
+
+  #[test]
+  fn position_taken() {
+    let mut layout = layout();
+    layout.fixtures[0].position = layout.seats[1].position;
+
+    assert_eq!(
+      layout.validate(3),
+      Err(Error::PositionTaken(layout.seats[1].position))
+    );
+  }
+
+  #[test]
+  fn deck_out_of_range() {
+    let mut layout = layout();
+    layout.seats[0].position.deck = 1;
+
+    assert_eq!(
+      layout.validate(3),
+      Err(Error::DeckOutOfRange(layout.seats[0].position))
+    );
+  }
+
+  #[test]
+  fn unknown_class() {
+    let mut layout = layout();
+    layout.seats[0].class_id = Some(ClassId(7));
+
+    assert_eq!(layout.validate(3), Err(Error::UnknownClass(ClassId(7))));
+  }
+
+  #[test]
+  fn duplicate_class() {
+    let mut layout = layout();
+    let class = |name: &str| Class {
+      id: ClassId(1),
+      name: name.to_string(),
+    };
+    layout.classes = vec![class("Cama"), class("Semi-cama")];
+
+    assert_eq!(layout.validate(3), Err(Error::DuplicateClass(ClassId(1))));
+  }
+
+  #[test]
+  fn maps_indices_and_labels() {
+    let layout = layout();
+
+    assert_eq!(layout.seat_index("1C"), Some(2));
+    assert_eq!(layout.seat(2).map(|s| s.window), Some(true));
+    assert_eq!(layout.seat_index("9Z"), None);
+  }
+}
@@ -1,14 +1,49 @@
-use crate::{bool_matrix::BoolMatrix, trip};
+use {
+  crate::{
+    bool_matrix::{self, BoolMatrix, DecodeError},
+    fare::{DepartureTier, LoadTier},
+    fare_type,
+    money::{Currency, Money},
+    order, passenger,
+    quote_fare::{Quote, Tax},
+    trip, varint,
+  },
+  std::time::Duration,
+};
+
+/// Bits of the byte that says which booking details follow the coordinates.
+const PASSENGER: u8 = 1;
+const ORDER: u8 = 1 << 1;
+const QUOTE: u8 = 1 << 2;
+const PROMO_CODE: u8 = 1 << 3;
+const TICKETED: u8 = 1 << 4;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SeatReservation {
   trip_id: trip::Id,
   seat: usize,
+  /// Boarding stop. The seat is taken on the legs leaving `from..to`, so it
+  /// is free again at `to`, where the passenger gets off.
   from: usize,
   to: usize,
+  /// Price paid, kept so the applied pricing tiers can be audited.
+  quote: Option<Quote>,
+  /// Code whose use the reservation holds, given back when it is released.
+  promo_code: Option<String>,
+  passenger_id: Option<passenger::Id>,
+  order_id: Option<order::Id>,
+  status: Status,
   pub matrix: BoolMatrix,
 }
 
+/// A seat occupies the trip either way; only a ticket has been paid for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Status {
+  #[default]
+  Held,
+  Ticketed,
+}
+
 pub struct SeatReservations {
   trip_id: trip::Id,
   pub matrix: BoolMatrix,
@@ -28,6 +63,21 @@ impl SeatReservations {
       matrix,
     })
   }
+
+  /// Seats taken on the busiest leg leaving `from..to`, as a percentage of
+  /// all seats.
+  pub fn load_percent(&self, from: usize, to: usize) -> u8 {
+    let seats = self.matrix.height;
+    let busiest = (from..to)
+      .map(|stop| {
+        (0..seats)
+          .filter(|&seat| self.matrix.get(stop, seat))
+          .count()
+      })
+      .max()
+      .unwrap_or(0);
+    u8::try_from(busiest * 100 / seats.max(1)).unwrap_or(100)
+  }
 }
 
 impl SeatReservation {
@@ -44,16 +94,35 @@ impl SeatReservation {
     stops: usize,
   ) -> Self {
     let mut matrix = BoolMatrix::new(stops, seats);
-    matrix.set_row_range(seat, from, to, true);
+    if from < to {
+      matrix.set_row_range(seat, from, to - 1, true);
+    }
     Self {
       trip_id,
       seat,
       from,
       to,
+      quote: None,
+      promo_code: None,
+      passenger_id: None,
+      order_id: None,
+      status: Status::Held,
       matrix,
     }
   }
 
+  pub const fn seat(&self) -> usize {
+    self.seat
+  }
+
+  pub const fn first_stop(&self) -> usize {
+    self.from
+  }
+
+  pub const fn last_stop(&self) -> usize {
+    self.to
+  }
+
   pub const fn seats(&self) -> usize {
     self.matrix.height
   }
@@ -61,4 +130,370 @@ impl SeatReservation {
   pub const fn stops(&self) -> usize {
     self.matrix.width
   }
+
+  pub const fn quote(&self) -> Option<&Quote> {
+    self.quote.as_ref()
+  }
+
+  pub fn set_quote(&mut self, quote: Quote) {
+    self.quote = Some(quote);
+  }
+
+  pub fn promo_code(&self) -> Option<&str> {
+    self.promo_code.as_deref()
+  }
+
+  pub fn set_promo_code(&mut self, promo_code: Option<String>) {
+    self.promo_code = promo_code;
+  }
+
+  pub const fn passenger_id(&self) -> Option<passenger::Id> {
+    self.passenger_id
+  }
+
+  pub const fn set_passenger_id(&mut self, passenger_id: Option<passenger::Id>) {
+    self.passenger_id = passenger_id;
+  }
+
+  pub const fn order_id(&self) -> Option<order::Id> {
+    self.order_id
+  }
+
+  pub const fn set_order_id(&mut self, order_id: Option<order::Id>) {
+    self.order_id = order_id;
+  }
+
+  pub const fn status(&self) -> Status {
+    self.status
+  }
+
+  pub const fn set_status(&mut self, status: Status) {
+    self.status = status;
+  }
+
+  /// The same booking on another seat of a bus with `seats` seats.
+  pub fn moved_to(&self, seat: usize, seats: usize) -> Self {
+    Self {
+      quote: self.quote.clone(),
+      promo_code: self.promo_code.clone(),
+      passenger_id: self.passenger_id,
+      order_id: self.order_id,
+      status: self.status,
+      ..Self::new(self.trip_id, seat, self.from, self.to, seats, self.stops())
+    }
+  }
+
+  /// A reservation only ever covers one seat over a stop range, so it is
+  /// stored as its coordinates and the matrix is rebuilt on decode. The
+  /// booking details follow, each only when set.
+  #[allow(dead_code)]
+  pub fn encode(&self) -> Vec<u8> {
+    let mut buffer: Vec<u8> = vec![];
+    varint::write(&mut buffer, self.trip_id.0);
+    for value in [self.seat, self.from, self.to, self.seats(), self.stops()] {
+      varint::write_usize(&mut buffer, value);
+    }
+
+    let flags = [
+      (PASSENGER, self.passenger_id.is_some()),
+      (ORDER, self.order_id.is_some()),
+      (QUOTE, self.quote.is_some()),
+      (PROMO_CODE, self.promo_code.is_some()),
+      (TICKETED, self.status == Status::Ticketed),
+    ]
+    .into_iter()
+    .filter(|&(_, set)| set)
+    .fold(0, |flags, (flag, _)| flags | flag);
+    buffer.push(flags);
+    if let Some(passenger_id) = self.passenger_id {
+      varint::write(&mut buffer, passenger_id.0);
+    }
+    if let Some(order_id) = self.order_id {
+      varint::write(&mut buffer, order_id.0);
+    }
+    if let Some(quote) = &self.quote {
+      write_quote(&mut buffer, quote);
+    }
+    if let Some(promo_code) = &self.promo_code {
+      write_string(&mut buffer, promo_code);
+    }
+    buffer
+  }
+
+  #[allow(dead_code)]
+  pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+    let mut cursor: usize = 0;
+    let trip_id = trip::Id(varint::read(bytes, &mut cursor).ok_or(DecodeError::Truncated)?);
+    let mut next = || varint::read_usize(bytes, &mut cursor).ok_or(DecodeError::Truncated);
+    let (seat, from, to, seats, stops) = (next()?, next()?, next()?, next()?, next()?);
+    if seat >= seats || from >= to || to >= stops {
+      return Err(DecodeError::InvalidPayload);
+    }
+    if !bool_matrix::fits(stops, seats) {
+      return Err(DecodeError::Oversized);
+    }
+    let mut reservation = Self::new(trip_id, seat, from, to, seats, stops);
+
+    let flags = *bytes.get(cursor).ok_or(DecodeError::Truncated)?;
+    cursor += 1;
+    if flags & !(PASSENGER | ORDER | QUOTE | PROMO_CODE | TICKETED) != 0 {
+      return Err(DecodeError::InvalidPayload);
+    }
+    if flags & PASSENGER != 0 {
+      reservation.passenger_id = Some(passenger::Id(read(bytes, &mut cursor)?));
+    }
+    if flags & ORDER != 0 {
+      reservation.order_id = Some(order::Id(read(bytes, &mut cursor)?));
+    }
+    if flags & QUOTE != 0 {
+      reservation.quote = Some(read_quote(bytes, &mut cursor)?);
+    }
+    if flags & PROMO_CODE != 0 {
+      reservation.promo_code = Some(read_string(bytes, &mut cursor)?);
+    }
+    if flags & TICKETED != 0 {
+      reservation.status = Status::Ticketed;
+    }
+    if cursor != bytes.len() {
+      return Err(DecodeError::InvalidPayload);
+    }
+
+    Ok(reservation)
+  }
+}
+
+fn read(bytes: &[u8], cursor: &mut usize) -> Result<u64, DecodeError> {
+  varint::read(bytes, cursor).ok_or(DecodeError::Truncated)
+}
+
+fn read_usize(bytes: &[u8], cursor: &mut usize) -> Result<usize, DecodeError> {
+  varint::read_usize(bytes, cursor).ok_or(DecodeError::Truncated)
+}
+
+/// A presence byte, then the value if there is one.
+fn write_option(buffer: &mut Vec<u8>, value: Option<u64>) {
+  buffer.push(value.is_some().into());
+  if let Some(value) = value {
+    varint::write(buffer, value);
+  }
+}
+
+fn read_option(bytes: &[u8], cursor: &mut usize) -> Result<Option<u64>, DecodeError> {
+  let present = *bytes.get(*cursor).ok_or(DecodeError::Truncated)?;
+  *cursor += 1;
+  match present {
+    0 => Ok(None),
+    1 => read(bytes, cursor).map(Some),
+    _ => Err(DecodeError::InvalidPayload),
+  }
+}
+
+fn write_load_tier(buffer: &mut Vec<u8>, tier: Option<LoadTier>) {
+  write_option(buffer, tier.map(|t| u64::from(t.min_load_percent)));
+  if let Some(tier) = tier {
+    varint::write(buffer, u64::from(tier.price_percent));
+  }
+}
+
+fn read_load_tier(bytes: &[u8], cursor: &mut usize) -> Result<Option<LoadTier>, DecodeError> {
+  read_option(bytes, cursor)?
+    .map(|min_load_percent| {
+      Ok(LoadTier {
+        min_load_percent: narrow(min_load_percent)?,
+        price_percent: narrow(read(bytes, cursor)?)?,
+      })
+    })
+    .transpose()
+}
+
+/// The window as whole seconds and the nanoseconds left over.
+fn write_departure_tier(buffer: &mut Vec<u8>, tier: Option<DepartureTier>) {
+  write_option(buffer, tier.map(|t| t.within.as_secs()));
+  if let Some(tier) = tier {
+    varint::write(buffer, u64::from(tier.within.subsec_nanos()));
+    varint::write(buffer, u64::from(tier.price_percent));
+  }
+}
+
+fn read_departure_tier(
+  bytes: &[u8],
+  cursor: &mut usize,
+) -> Result<Option<DepartureTier>, DecodeError> {
+  read_option(bytes, cursor)?
+    .map(|secs| {
+      let nanos: u32 = narrow(read(bytes, cursor)?)?;
+      if nanos >= 1_000_000_000 {
+        return Err(DecodeError::InvalidPayload);
+      }
+      Ok(DepartureTier {
+        within: Duration::new(secs, nanos),
+        price_percent: narrow(read(bytes, cursor)?)?,
+      })
+    })
+    .transpose()
+}
+
+fn narrow<T: TryFrom<u64>>(value: u64) -> Result<T, DecodeError> {
+  T::try_from(value).map_err(|_| DecodeError::InvalidPayload)
+}
+
+fn write_money(buffer: &mut Vec<u8>, money: Money) {
+  varint::write(buffer, money.amount);
+  buffer.extend(money.currency.0);
+}
+
+fn read_money(bytes: &[u8], cursor: &mut usize) -> Result<Money, DecodeError> {
+  let amount = read(bytes, cursor)?;
+  let code: [u8; 3] = bytes
+    .get(*cursor..*cursor + 3)
+    .and_then(|code| code.try_into().ok())
+    .ok_or(DecodeError::Truncated)?;
+  *cursor += 3;
+  Ok(Money::new(amount, Currency(code)))
+}
+
+fn write_string(buffer: &mut Vec<u8>, string: &str) {
+  varint::write_usize(buffer, string.len());
+  buffer.extend(string.as_bytes());
+}
+
+fn read_string(bytes: &[u8], cursor: &mut usize) -> Result<String, DecodeError> {
+  let len = read_usize(bytes, cursor)?;
+  let string = cursor
+    .checked_add(len)
+    .and_then(|end| bytes.get(*cursor..end))
+    .ok_or(DecodeError::Truncated)?;
+  *cursor += len;
+  String::from_utf8(string.to_vec()).map_err(|_| DecodeError::InvalidPayload)
+}
+
+fn write_quote(buffer: &mut Vec<u8>, quote: &Quote) {
+  buffer.push(quote.load_percent);
+  write_load_tier(buffer, quote.load_tier);
+  write_departure_tier(buffer, quote.departure_tier);
+  write_money(buffer, quote.base);
+  write_option(buffer, quote.fare_type_id.map(|id| id.0));
+  write_money(buffer, quote.discount);
+  varint::write_usize(buffer, quote.taxes.len());
+  for tax in &quote.taxes {
+    write_string(buffer, &tax.name);
+    write_money(buffer, tax.amount);
+  }
+  write_money(buffer, quote.total);
+}
+
+fn read_quote(bytes: &[u8], cursor: &mut usize) -> Result<Quote, DecodeError> {
+  let load_percent = *bytes.get(*cursor).ok_or(DecodeError::Truncated)?;
+  *cursor += 1;
+  let load_tier = read_load_tier(bytes, cursor)?;
+  let departure_tier = read_departure_tier(bytes, cursor)?;
+  let base = read_money(bytes, cursor)?;
+  let fare_type_id = read_option(bytes, cursor)?.map(fare_type::Id);
+  let discount = read_money(bytes, cursor)?;
+  let taxes = (0..read_usize(bytes, cursor)?)
+    .map(|_| {
+      Ok(Tax {
+        name: read_string(bytes, cursor)?,
+        amount: read_money(bytes, cursor)?,
+      })
+    })
+    .collect::<Result<Vec<Tax>, DecodeError>>()?;
+  let total = read_money(bytes, cursor)?;
+  Ok(Quote {
+    load_percent,
+    load_tier,
+    departure_tier,
+    base,
+    fare_type_id,
+    discount,
+    taxes,
+    total,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encode_round_trip() {
+    let reservation = SeatReservation::new(trip::Id(300), 12, 1, 3, 40, 5);
+    let encoded = reservation.encode();
+
+    assert!(encoded.len() < reservation.matrix.data().len());
+    assert_eq!(SeatReservation::decode(&encoded), Ok(reservation.clone()));
+
+    let clp = Currency(*b"CLP");
+    let mut booked = reservation;
+    booked.set_quote(Quote {
+      load_percent: 85,
+      load_tier: Some(LoadTier {
+        min_load_percent: 80,
+        price_percent: 125,
+      }),
+      departure_tier: Some(DepartureTier {
+        within: Duration::from_millis(86_400_500),
+        price_percent: 90,
+      }),
+      base: Money::new(12_000, clp),
+      fare_type_id: Some(fare_type::Id(2)),
+      discount: Money::new(3_000, clp),
+      taxes: vec![Tax {
+        name: "IVA".to_string(),
+        amount: Money::new(1_710, clp),
+      }],
+      total: Money::new(10_710, clp),
+    });
+    booked.set_promo_code(Some("VERANO".to_string()));
+    booked.set_passenger_id(Some(passenger::Id(7)));
+    booked.set_order_id(Some(order::Id(u64::MAX)));
+    booked.set_status(Status::Ticketed);
+    let encoded = booked.encode();
+
+    assert_eq!(SeatReservation::decode(&encoded), Ok(booked));
+    assert_eq!(
+      SeatReservation::decode(&encoded[..encoded.len() - 1]),
+      Err(DecodeError::Truncated)
+    );
+  }
+
+  #[test]
+  fn decode_rejects_out_of_bounds_seat() {
+    let mut encoded: Vec<u8> = vec![];
+    for value in [0, 4, 0, 1, 4, 2, 0] {
+      varint::write(&mut encoded, value);
+    }
+
+    assert_eq!(
+      SeatReservation::decode(&encoded),
+      Err(DecodeError::InvalidPayload)
+    );
+    assert_eq!(
+      SeatReservation::decode(&encoded[..3]),
+      Err(DecodeError::Truncated)
+    );
+  }
+
+  #[test]
+  fn decode_rejects_huge_dimensions() {
+    let mut encoded: Vec<u8> = vec![];
+    for value in [0, 0, 0, 1, 1 << 16, 1 << 16] {
+      varint::write(&mut encoded, value);
+    }
+
+    assert_eq!(
+      SeatReservation::decode(&encoded),
+      Err(DecodeError::Oversized)
+    );
+
+    let mut encoded: Vec<u8> = vec![];
+    for value in [0, 0, 0, 1, 0xFFFF, 0xFFFF] {
+      varint::write(&mut encoded, value);
+    }
+
+    assert_eq!(
+      SeatReservation::decode(&encoded),
+      Err(DecodeError::Oversized)
+    );
+  }
 }
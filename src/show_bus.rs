@@ -1,5 +1,7 @@
-use crate::{bus::{self, Bus}, Database};
-
+use crate::{
+  bus::{self, Bus},
+  Database,
+};
 
 pub trait ShowBus {
   fn show_bus(&self, id: bus::Id) -> Option<&Bus>;
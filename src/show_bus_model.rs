@@ -30,11 +30,21 @@ mod tests {
       id: bus_model::Id(0),
       name: String::default(),
       number_of_seats: 1,
+      layout: None,
     };
     let database: Database = Database {
       bus_models: vec![bus_model],
       ..Default::default()
     };
-    assert_eq!(database.show_bus_model(bus_model::Id(0)), Some(BusModel { id: bus_model::Id(0), name: String::default(), number_of_seats: 1 }).as_ref());
+    assert_eq!(
+      database.show_bus_model(bus_model::Id(0)),
+      Some(BusModel {
+        id: bus_model::Id(0),
+        name: String::default(),
+        number_of_seats: 1,
+        layout: None
+      })
+      .as_ref()
+    );
   }
 }
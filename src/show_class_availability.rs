@@ -0,0 +1,220 @@
+use crate::{
+  reserve_seat::{Error, ReserveSeat},
+  seat_layout::{ClassId, PassengerAttribute},
+  time::Timestamp,
+  trip, Database,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClassAvailability {
+  pub class_id: ClassId,
+  /// Free seats of the class on the leg leaving each stop of the route, so
+  /// all of them at the last stop.
+  pub free_by_stop: Vec<usize>,
+}
+
+#[allow(dead_code)]
+pub trait ShowClassAvailability {
+  /// Seats neither reserved nor blocked, counting restricted seats only once
+  /// they are released to everyone at `at`.
+  fn show_class_availability(
+    &self,
+    trip_id: trip::Id,
+    at: Timestamp,
+  ) -> Result<Vec<ClassAvailability>, Error>;
+
+  /// Seats of the class that are free on every leg from `from` to `to` and
+  /// that a passenger with `passenger_attributes` may take at `at`.
+  fn count_class_available(
+    &self,
+    trip_id: trip::Id,
+    class_id: ClassId,
+    from: usize,
+    to: usize,
+    passenger_attributes: &[PassengerAttribute],
+    at: Timestamp,
+  ) -> Result<usize, Error>;
+}
+
+impl ShowClassAvailability for Database {
+  fn show_class_availability(
+    &self,
+    trip_id: trip::Id,
+    at: Timestamp,
+  ) -> Result<Vec<ClassAvailability>, Error> {
+    let aggr = self.build_aggregate(trip_id)?;
+    let occupancy = self.build_occupancy(trip_id, &aggr)?.matrix;
+    let blocks = self.build_blocks(trip_id, &aggr);
+    let Some(layout) = aggr.bus_model().layout.as_ref() else {
+      return Ok(vec![]);
+    };
+
+    Ok(
+      layout
+        .classes
+        .iter()
+        .map(|class| {
+          let seats = layout.seat_indices_of_class(class.id);
+          ClassAvailability {
+            class_id: class.id,
+            free_by_stop: (0..aggr.stops_count())
+              .map(|stop| {
+                seats
+                  .iter()
+                  .filter(|&&seat| {
+                    !occupancy.get(stop, seat)
+                      && !blocks.get(stop, seat)
+                      && aggr.unmet_restriction(seat, stop, &[], at).is_none()
+                  })
+                  .count()
+              })
+              .collect(),
+          }
+        })
+        .collect(),
+    )
+  }
+
+  fn count_class_available(
+    &self,
+    trip_id: trip::Id,
+    class_id: ClassId,
+    from: usize,
+    to: usize,
+    passenger_attributes: &[PassengerAttribute],
+    at: Timestamp,
+  ) -> Result<usize, Error> {
+    let aggr = self.build_aggregate(trip_id)?;
+    if from >= aggr.stops_count() {
+      return Err(Error::InvalidFromStop);
+    }
+    if to >= aggr.stops_count() || to <= from {
+      return Err(Error::InvalidToStop);
+    }
+    let occupancy = self.build_occupancy(trip_id, &aggr)?.matrix;
+    let blocks = self.build_blocks(trip_id, &aggr);
+    let layout = aggr
+      .bus_model()
+      .layout
+      .as_ref()
+      .ok_or(Error::InvalidClass)?;
+
+    Ok(
+      layout
+        .seat_indices_of_class(class_id)
+        .into_iter()
+        .filter(|&seat| {
+          aggr
+            .unmet_restriction(seat, from, passenger_attributes, at)
+            .is_none()
+        })
+        .filter(|&seat| {
+          (from..to).all(|stop| !occupancy.get(stop, seat) && !blocks.get(stop, seat))
+        })
+        .count(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      fixtures::{class_database, CAMA, SEMI_CAMA},
+      reserve_seat_of_class::{self, ReserveSeatOfClass},
+      seat_block::{self, Reason, SeatBlock},
+      seat_layout::Restriction,
+    },
+    std::time::Duration,
+  };
+
+  #[test]
+  fn counts_per_class_and_stop() {
+    let mut database = class_database();
+    let _ = database.reserve_seat_of_class(reserve_seat_of_class::Form {
+      class_id: CAMA,
+      to_stop_index: 1,
+      ..Default::default()
+    });
+    let _ = database.reserve_seat_of_class(reserve_seat_of_class::Form {
+      class_id: SEMI_CAMA,
+      from_stop_index: 1,
+      to_stop_index: 2,
+      ..Default::default()
+    });
+
+    assert_eq!(
+      database.show_class_availability(trip::Id(0), Timestamp(0)),
+      Ok(vec![
+        ClassAvailability {
+          class_id: CAMA,
+          free_by_stop: vec![1, 2, 2],
+        },
+        ClassAvailability {
+          class_id: SEMI_CAMA,
+          free_by_stop: vec![3, 2, 3],
+        },
+      ])
+    );
+    assert_eq!(
+      database.count_class_available(trip::Id(0), SEMI_CAMA, 0, 2, &[], Timestamp(0)),
+      Ok(2)
+    );
+    assert_eq!(
+      database.count_class_available(trip::Id(0), CAMA, 1, 2, &[], Timestamp(0)),
+      Ok(2)
+    );
+    assert_eq!(
+      database.count_class_available(trip::Id(0), CAMA, 2, 1, &[], Timestamp(0)),
+      Err(Error::InvalidToStop)
+    );
+  }
+
+  #[test]
+  fn leaves_out_blocked_and_restricted_seats() {
+    let mut database = class_database();
+    database.seat_blocks.push(SeatBlock {
+      id: seat_block::Id(0),
+      trip_id: trip::Id(0),
+      seat: 0,
+      from: 1,
+      to: 2,
+      reason: Reason::OutOfService,
+    });
+    if let Some(layout) = database.bus_models[0].layout.as_mut() {
+      layout.seats[1].restriction = Some(Restriction {
+        requires: PassengerAttribute::Wheelchair,
+        release_before: Some(Duration::from_hours(1)),
+      });
+    }
+    let departure = Timestamp(100_000);
+    database.trips[0].departure = departure;
+
+    assert_eq!(
+      database
+        .show_class_availability(trip::Id(0), Timestamp(0))
+        .map(|classes| classes[0].free_by_stop.clone()),
+      Ok(vec![1, 0, 1])
+    );
+    assert_eq!(
+      database.count_class_available(trip::Id(0), CAMA, 0, 1, &[], Timestamp(0)),
+      Ok(1)
+    );
+    assert_eq!(
+      database.count_class_available(
+        trip::Id(0),
+        CAMA,
+        0,
+        2,
+        &[PassengerAttribute::Wheelchair],
+        Timestamp(0)
+      ),
+      Ok(1)
+    );
+    assert_eq!(
+      database.count_class_available(trip::Id(0), CAMA, 0, 2, &[], departure),
+      Ok(1)
+    );
+  }
+}
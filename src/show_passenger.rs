@@ -0,0 +1,24 @@
+use crate::{
+  passenger::{self, Document, Passenger},
+  Database,
+};
+
+pub trait ShowPassenger {
+  fn show_passenger(&self, id: passenger::Id) -> Option<&Passenger>;
+
+  /// Matches document numbers regardless of separators and case.
+  fn find_passenger_by_document(&self, document: &Document) -> Option<&Passenger>;
+}
+
+impl ShowPassenger for Database {
+  fn show_passenger(&self, id: passenger::Id) -> Option<&Passenger> {
+    self.passengers.iter().find(|p| p.id == id)
+  }
+
+  fn find_passenger_by_document(&self, document: &Document) -> Option<&Passenger> {
+    self
+      .passengers
+      .iter()
+      .find(|p| p.document.same_as(document))
+  }
+}
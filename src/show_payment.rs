@@ -0,0 +1,28 @@
+use crate::{money::Money, order, payment::PaymentIntent, Database};
+
+pub trait ShowPayment {
+  /// The order's payment whose funds were taken, if any.
+  fn captured_payment(&self, order_id: order::Id) -> Option<&PaymentIntent>;
+
+  /// What the order's captured payments can still give back, `None` when it
+  /// has none or they are in different currencies.
+  fn refundable(&self, order_id: order::Id) -> Option<Money>;
+}
+
+impl ShowPayment for Database {
+  fn captured_payment(&self, order_id: order::Id) -> Option<&PaymentIntent> {
+    self
+      .payments
+      .iter()
+      .find(|p| p.order_id == order_id && p.is_captured())
+  }
+
+  fn refundable(&self, order_id: order::Id) -> Option<Money> {
+    self
+      .payments
+      .iter()
+      .filter(|p| p.order_id == order_id && p.is_captured())
+      .map(|p| p.amount.checked_sub(p.refunded))
+      .reduce(|total, left| total?.checked_add(left?))?
+  }
+}
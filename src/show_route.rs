@@ -5,10 +5,25 @@ use crate::{
 
 pub trait ShowRoute {
   fn show_route(&self, id: route::Id) -> Option<&Route>;
+
+  /// The route at the root of the variant chain `id` belongs to.
+  fn corridor_of(&self, id: route::Id) -> route::Id;
 }
 
 impl ShowRoute for Database {
   fn show_route(&self, id: route::Id) -> Option<&Route> {
     self.routes.iter().find(|r| r.id == id)
   }
+
+  fn corridor_of(&self, id: route::Id) -> route::Id {
+    let mut corridor = id;
+    // Bounded so that a cycle of variants cannot loop forever.
+    for _ in 0..self.routes.len() {
+      match self.show_route(corridor).and_then(|r| r.base_id) {
+        Some(base_id) => corridor = base_id,
+        None => break,
+      }
+    }
+    corridor
+  }
 }
@@ -0,0 +1,119 @@
+use {
+  crate::{route, Database},
+  std::time::Duration,
+};
+
+/// The stretch between two consecutive stops of a route.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Leg {
+  pub from: route::StopId,
+  pub to: route::StopId,
+  pub distance_meters: u32,
+  pub running_time: Duration,
+}
+
+pub trait ShowRouteLegs {
+  #[allow(dead_code)]
+  fn show_route_legs(&self, id: route::Id) -> Vec<Leg>;
+
+  /// Distance travelled between two stop indices of the route.
+  fn route_distance(&self, id: route::Id, from: usize, to: usize) -> u64;
+
+  /// Arrival offset at each stop derived from running times plus the dwell
+  /// time scheduled at every earlier stop.
+  #[allow(dead_code)]
+  fn estimate_arrival_offsets(&self, id: route::Id) -> Vec<Duration>;
+}
+
+fn sorted_route_stops(database: &Database, id: route::Id) -> Vec<&route::Stop> {
+  let mut route_stops: Vec<&route::Stop> = database
+    .route_stops
+    .iter()
+    .filter(|rs| rs.route_id == id)
+    .collect();
+  route_stops.sort_by_key(|rs| rs.index);
+  route_stops
+}
+
+impl ShowRouteLegs for Database {
+  fn show_route_legs(&self, id: route::Id) -> Vec<Leg> {
+    sorted_route_stops(self, id)
+      .windows(2)
+      .map(|pair| Leg {
+        from: pair[0].id,
+        to: pair[1].id,
+        distance_meters: pair[1].leg_distance_meters,
+        running_time: pair[1].leg_running_time,
+      })
+      .collect()
+  }
+
+  fn route_distance(&self, id: route::Id, from: usize, to: usize) -> u64 {
+    sorted_route_stops(self, id)
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| from < *i && *i <= to)
+      .map(|(_, rs)| u64::from(rs.leg_distance_meters))
+      .sum()
+  }
+
+  fn estimate_arrival_offsets(&self, id: route::Id) -> Vec<Duration> {
+    let mut elapsed = Duration::ZERO;
+    let mut previous: Option<&route::Stop> = None;
+    sorted_route_stops(self, id)
+      .into_iter()
+      .map(|rs| {
+        if let Some(previous) = previous {
+          let dwell = previous
+            .departure_offset
+            .saturating_sub(previous.arrival_offset);
+          elapsed += dwell + rs.leg_running_time;
+        }
+        previous = Some(rs);
+        elapsed
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::fixtures::route_stops};
+
+  #[test]
+  fn legs_distances_and_arrivals() {
+    let database: Database = Database {
+      route_stops: route_stops(),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.show_route_legs(route::Id(0)),
+      vec![
+        Leg {
+          from: route::StopId(0),
+          to: route::StopId(1),
+          distance_meters: 40_000,
+          running_time: Duration::from_mins(30),
+        },
+        Leg {
+          from: route::StopId(1),
+          to: route::StopId(2),
+          distance_meters: 60_000,
+          running_time: Duration::from_mins(50),
+        },
+      ]
+    );
+    assert_eq!(database.route_distance(route::Id(0), 0, 2), 100_000);
+    assert_eq!(database.route_distance(route::Id(0), 1, 2), 60_000);
+    assert_eq!(database.route_distance(route::Id(0), 1, 1), 0);
+    assert_eq!(
+      database.estimate_arrival_offsets(route::Id(0)),
+      vec![
+        Duration::ZERO,
+        Duration::from_mins(30),
+        Duration::from_mins(85)
+      ]
+    );
+  }
+}
@@ -0,0 +1,103 @@
+use {
+  crate::{
+    bool_matrix::BoolMatrix,
+    reserve_seat::{Error, ReserveSeat},
+    trip, Database,
+  },
+  std::fmt,
+};
+
+/// Seats × stops view of a trip with passenger reservations and staff blocks
+/// kept as separate layers.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SeatMap {
+  pub reserved: BoolMatrix,
+  pub blocked: BoolMatrix,
+}
+
+impl SeatMap {
+  #[allow(dead_code)]
+  pub fn is_free(&self, seat: usize, stop: usize) -> bool {
+    !self.reserved.get(stop, seat) && !self.blocked.get(stop, seat)
+  }
+}
+
+/// One line per seat: `#` reserved, `x` blocked, `.` free.
+impl fmt::Display for SeatMap {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for seat in 0..self.reserved.height {
+      if seat > 0 {
+        writeln!(f)?;
+      }
+      for stop in 0..self.reserved.width {
+        let cell = if self.reserved.get(stop, seat) {
+          '#'
+        } else if self.blocked.get(stop, seat) {
+          'x'
+        } else {
+          '.'
+        };
+        write!(f, "{cell}")?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[allow(dead_code)]
+pub trait ShowSeatMap {
+  fn show_seat_map(&self, trip_id: trip::Id) -> Result<SeatMap, Error>;
+}
+
+impl ShowSeatMap for Database {
+  fn show_seat_map(&self, trip_id: trip::Id) -> Result<SeatMap, Error> {
+    let aggr = self.build_aggregate(trip_id)?;
+
+    Ok(SeatMap {
+      reserved: self.build_occupancy(trip_id, &aggr)?.matrix,
+      blocked: self.build_blocks(trip_id, &aggr),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      block_seat::{self, BlockSeat},
+      bus_model::BusModel,
+      fixtures::database,
+      reserve_seat,
+      seat_block::Reason,
+    },
+  };
+
+  #[test]
+  fn renders_reservations_and_blocks() {
+    let mut database = database();
+    database.bus_models = vec![BusModel {
+      number_of_seats: 2,
+      ..Default::default()
+    }];
+    let _ = database.reserve_seat(reserve_seat::Form {
+      to_stop_index: 1,
+      ..Default::default()
+    });
+    let _ = database.block_seat(block_seat::Form {
+      trip_id: trip::Id(0),
+      seat_index: 1,
+      from_stop_index: 0,
+      to_stop_index: 1,
+      reason: Reason::Other("Driver relief".to_string()),
+    });
+
+    let seat_map = database.show_seat_map(trip::Id(0));
+
+    assert_eq!(
+      seat_map.as_ref().map(ToString::to_string),
+      Ok("#.\nx.".to_string())
+    );
+    assert_eq!(seat_map.map(|m| m.is_free(0, 1)), Ok(true));
+  }
+}
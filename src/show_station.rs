@@ -0,0 +1,61 @@
+use crate::{show_stop::ShowStop, stop, Database};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Station<'a> {
+  pub stop: &'a stop::Stop,
+  pub platforms: Vec<&'a stop::Stop>,
+}
+
+pub trait ShowStation {
+  #[allow(dead_code)]
+  fn show_station(&self, id: stop::Id) -> Option<Station<'_>>;
+
+  /// The station a platform belongs to, or the stop itself.
+  fn station_of(&self, id: stop::Id) -> stop::Id;
+}
+
+impl ShowStation for Database {
+  fn show_station(&self, id: stop::Id) -> Option<Station<'_>> {
+    let stop = self.show_stop(self.station_of(id))?;
+
+    Some(Station {
+      stop,
+      platforms: self
+        .stops
+        .iter()
+        .filter(|s| s.parent_id == Some(stop.id))
+        .collect(),
+    })
+  }
+
+  fn station_of(&self, id: stop::Id) -> stop::Id {
+    self.show_stop(id).and_then(|s| s.parent_id).unwrap_or(id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::fixtures::stops};
+
+  #[test]
+  fn from_station_or_platform() {
+    let database: Database = Database {
+      stops: stops(),
+      ..Default::default()
+    };
+    let platform_ids =
+      |station: Station| station.platforms.iter().map(|p| p.id).collect::<Vec<_>>();
+
+    assert_eq!(
+      database.show_station(stop::Id(2)).map(platform_ids),
+      Some(vec![stop::Id(1), stop::Id(2)])
+    );
+    assert_eq!(
+      database.show_station(stop::Id(3)).map(platform_ids),
+      Some(vec![])
+    );
+    assert_eq!(database.station_of(stop::Id(1)), stop::Id(0));
+    assert!(database.show_station(stop::Id(9)).is_none());
+  }
+}
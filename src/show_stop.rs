@@ -1,7 +1,4 @@
-use crate::{
-  stop,
-  Database,
-};
+use crate::{stop, Database};
 
 pub trait ShowStop {
   fn show_stop(&self, id: stop::Id) -> Option<&stop::Stop>;
@@ -0,0 +1,17 @@
+use crate::{trip, Database};
+
+pub trait ShowTicket {
+  /// Position in `seat_reservations` of the reservation holding the seat on
+  /// the leg leaving `stop_index`.
+  fn find_ticket(&self, trip_id: trip::Id, seat_index: usize, stop_index: usize) -> Option<usize>;
+}
+
+impl ShowTicket for Database {
+  fn find_ticket(&self, trip_id: trip::Id, seat_index: usize, stop_index: usize) -> Option<usize> {
+    self.seat_reservations.iter().position(|sr| {
+      sr.trip_id() == trip_id
+        && sr.seat() == seat_index
+        && (sr.first_stop()..sr.last_stop()).contains(&stop_index)
+    })
+  }
+}
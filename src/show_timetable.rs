@@ -0,0 +1,112 @@
+use crate::{
+  route,
+  show_trip::ShowTrip,
+  stop,
+  time::Timestamp,
+  trip::{self, Trip},
+  Database,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+  pub route_stop_id: route::StopId,
+  pub stop_id: stop::Id,
+  pub arrival: Timestamp,
+  pub departure: Timestamp,
+}
+
+pub trait ShowTimetable {
+  #[allow(dead_code)]
+  fn show_timetable(&self, id: trip::Id) -> Option<Vec<Entry>>;
+
+  fn timetable(&self, trip: &Trip) -> Vec<Entry>;
+}
+
+impl ShowTimetable for Database {
+  fn show_timetable(&self, id: trip::Id) -> Option<Vec<Entry>> {
+    let trip: &Trip = self.show_trip(id)?;
+    Some(self.timetable(trip))
+  }
+
+  fn timetable(&self, trip: &Trip) -> Vec<Entry> {
+    let mut route_stops: Vec<&route::Stop> = self
+      .route_stops
+      .iter()
+      .filter(|rs| rs.route_id == trip.route_id)
+      .collect();
+    route_stops.sort_by_key(|rs| rs.index);
+
+    route_stops
+      .into_iter()
+      .enumerate()
+      .map(|(i, rs)| Entry {
+        route_stop_id: rs.id,
+        stop_id: match trip.departure_platform {
+          Some(platform) if i == 0 => platform,
+          _ => rs.stop_id,
+        },
+        arrival: trip.departure.add(rs.arrival_offset),
+        departure: trip.departure.add(rs.departure_offset),
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, std::time::Duration};
+
+  #[test]
+  fn none_test() {
+    let database: Database = Database::default();
+
+    assert_eq!(database.show_timetable(trip::Id(0)), None);
+  }
+
+  #[test]
+  fn ordered_by_stop_index() {
+    let database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        departure: Timestamp(1_000),
+        ..Default::default()
+      }],
+      route_stops: vec![
+        route::Stop {
+          id: route::StopId(1),
+          stop_id: stop::Id(1),
+          index: 1,
+          arrival_offset: Duration::from_mins(10),
+          departure_offset: Duration::from_mins(11),
+          ..Default::default()
+        },
+        route::Stop {
+          id: route::StopId(0),
+          stop_id: stop::Id(0),
+          index: 0,
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    };
+
+    assert_eq!(
+      database.show_timetable(trip::Id(0)),
+      Some(vec![
+        Entry {
+          route_stop_id: route::StopId(0),
+          stop_id: stop::Id(0),
+          arrival: Timestamp(1_000),
+          departure: Timestamp(1_000),
+        },
+        Entry {
+          route_stop_id: route::StopId(1),
+          stop_id: stop::Id(1),
+          arrival: Timestamp(1_600),
+          departure: Timestamp(1_660),
+        },
+      ])
+    );
+  }
+}
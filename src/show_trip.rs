@@ -1,4 +1,7 @@
-use crate::{trip::{self, Trip}, Database};
+use crate::{
+  trip::{self, Trip},
+  Database,
+};
 
 pub trait ShowTrip {
   fn show_trip(&self, id: trip::Id) -> Option<&Trip>;
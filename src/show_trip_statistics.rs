@@ -0,0 +1,79 @@
+use crate::{
+  reserve_seat::Error, show_route_legs::ShowRouteLegs, show_trip::ShowTrip, trip, Database,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Statistics {
+  pub passengers: usize,
+  /// Sum over reservations of the distance each passenger travels.
+  pub passenger_meters: u64,
+}
+
+impl Statistics {
+  #[allow(clippy::cast_precision_loss)]
+  #[allow(dead_code)]
+  pub fn passenger_kilometres(&self) -> f64 {
+    self.passenger_meters as f64 / 1_000.0
+  }
+}
+
+#[allow(dead_code)]
+pub trait ShowTripStatistics {
+  fn show_trip_statistics(&self, id: trip::Id) -> Result<Statistics, Error>;
+}
+
+impl ShowTripStatistics for Database {
+  fn show_trip_statistics(&self, id: trip::Id) -> Result<Statistics, Error> {
+    let trip = self.show_trip(id).ok_or(Error::TripNotFound)?;
+    let reservations: Vec<_> = self
+      .seat_reservations
+      .iter()
+      .filter(|sr| sr.trip_id() == id)
+      .collect();
+
+    Ok(Statistics {
+      passengers: reservations.len(),
+      passenger_meters: reservations
+        .iter()
+        .map(|sr| self.route_distance(trip.route_id, sr.first_stop(), sr.last_stop()))
+        .sum(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{fixtures::route_stops, route, seat_reservation::SeatReservation, trip::Trip},
+  };
+
+  #[test]
+  fn passenger_kilometres() {
+    let database: Database = Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: route_stops(),
+      seat_reservations: vec![
+        SeatReservation::new(trip::Id(0), 0, 0, 2, 2, 3),
+        SeatReservation::new(trip::Id(0), 1, 0, 1, 2, 3),
+        SeatReservation::new(trip::Id(1), 1, 0, 2, 2, 3),
+      ],
+      ..Default::default()
+    };
+
+    let statistics = database.show_trip_statistics(trip::Id(0));
+
+    assert_eq!(
+      statistics,
+      Ok(Statistics {
+        passengers: 2,
+        passenger_meters: 140_000,
+      })
+    );
+    assert!(statistics.is_ok_and(|s| (s.passenger_kilometres() - 140.0).abs() < f64::EPSILON));
+  }
+}
@@ -1,8 +1,19 @@
-#[derive(Debug, Default, PartialEq, Eq)]
+use crate::{geo::Coordinates, time::TimeZone};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Id(pub u64);
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[allow(dead_code)]
 pub struct Stop {
   pub id: Id,
   pub name: String,
+  /// Other names customers know the stop by, e.g. "stgo".
+  pub aliases: Vec<String>,
+  pub address: String,
+  pub location: Option<Coordinates>,
+  /// Local time at the stop.
+  pub time_zone: TimeZone,
+  /// The station this stop is a platform or bay of.
+  pub parent_id: Option<Id>,
 }
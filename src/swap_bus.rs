@@ -0,0 +1,625 @@
+use {
+  crate::{
+    bool_matrix::BoolMatrix,
+    bus::{self, Bus},
+    bus_assignment::{CheckBusAssignment, Conflict},
+    bus_model::BusModel,
+    cancel_ticket::{self, CancelTicket},
+    order, payment,
+    payment_provider::PaymentProvider,
+    place_order::PlaceOrder,
+    process_payment::{self, ProcessPayment},
+    refund::Refund,
+    reserve_seat::{self, Aggregate, ReserveSeat},
+    seat_block::SeatBlock,
+    seat_layout::{ClassId, PassengerAttribute},
+    seat_reservation::{self, SeatReservation},
+    show_bus::ShowBus,
+    show_bus_model::ShowBusModel,
+    show_passenger::ShowPassenger,
+    show_trip::ShowTrip,
+    time::Timestamp,
+    trip::{self, Trip, Vehicle},
+    Database,
+  },
+  std::{error, fmt::Display, time::Duration},
+};
+
+pub struct Form {
+  pub trip_id: trip::Id,
+  pub bus_id: bus::Id,
+  pub turnaround: Duration,
+  /// When the swap happens, for restricted seat release and refunds.
+  pub requested_at: Timestamp,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+  TripNotFound,
+  BusNotFound,
+  BusModelNotFound,
+  BusUnavailable(Conflict),
+  Reservation(reserve_seat::Error),
+  /// A ticket that fits nowhere on the new bus could not be cancelled.
+  Ticket(cancel_ticket::Error),
+  /// The payment of an order losing held seats could not follow its total.
+  Payment(process_payment::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TripNotFound => write!(f, "trip not found"),
+      Self::BusNotFound => write!(f, "bus not found"),
+      Self::BusModelNotFound => write!(f, "bus model not found"),
+      Self::BusUnavailable(conflict) => write!(f, "bus unavailable: {conflict:?}"),
+      Self::Reservation(error) => write!(f, "{error}"),
+      Self::Ticket(error) => write!(f, "{error}"),
+      Self::Payment(error) => write!(f, "{error}"),
+    }
+  }
+}
+impl error::Error for Error {}
+
+/// What did not make it onto the new bus.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Swap {
+  /// Tickets that fit nowhere, cancelled and refunded by the route's policy.
+  pub cancelled: Vec<Refund>,
+  /// Held seats that fit nowhere, taken out of their orders.
+  pub released: Vec<SeatReservation>,
+  /// Staff blocks on seats or stops the new bus lacks.
+  pub dropped_blocks: Vec<SeatBlock>,
+}
+
+#[allow(dead_code)]
+pub trait SwapBus {
+  /// Assigns another bus to a trip and migrates its blocks and reservations
+  /// to the new seat layout. Seats are matched by label, or by index when
+  /// either bus has no layout. Blocks keep their seat; passengers keep
+  /// theirs when it exists, is free and unblocked, is of the same class and
+  /// they may take it, otherwise they get the lowest such seat for their
+  /// stops. Passengers who fit nowhere have their tickets cancelled or held
+  /// seats released, and their orders' open payments follow the new totals.
+  ///
+  /// Everything is checked before the provider is called. A refund or void
+  /// that still fails stops the swap with the earlier ones made and the old
+  /// bus still assigned.
+  fn swap_bus(&mut self, form: Form, provider: &mut dyn PaymentProvider) -> Result<Swap, Error>;
+}
+
+impl SwapBus for Database {
+  fn swap_bus(&mut self, form: Form, provider: &mut dyn PaymentProvider) -> Result<Swap, Error> {
+    let trip: &Trip = self.show_trip(form.trip_id).ok_or(Error::TripNotFound)?;
+    let bus: &Bus = self.show_bus(form.bus_id).ok_or(Error::BusNotFound)?;
+    let bus_model: &BusModel = self
+      .show_bus_model(bus.bus_model_id)
+      .ok_or(Error::BusModelNotFound)?;
+    let swapped = Trip {
+      vehicle: Vehicle::Bus(form.bus_id),
+      ..*trip
+    };
+    self
+      .check_bus_assignment(&swapped, form.turnaround)
+      .map_err(Error::BusUnavailable)?;
+    let Plan {
+      kept,
+      tickets,
+      released,
+      blocks,
+      dropped_blocks,
+    } = plan(self, &form, bus_model)?;
+    self
+      .check_cancellations(&tickets, form.requested_at)
+      .map_err(Error::Ticket)?;
+    let mut orders: Vec<order::Id> = released
+      .iter()
+      .filter_map(SeatReservation::order_id)
+      .collect();
+    orders.sort_unstable_by_key(|id| id.0);
+    orders.dedup();
+    let authorized: Vec<payment::Id> = self
+      .payments
+      .iter()
+      .filter(|p| orders.contains(&p.order_id) && p.status == payment::Status::Authorized)
+      .map(|p| p.id)
+      .collect();
+
+    let mut cancelled: Vec<Refund> = Vec::with_capacity(tickets.len());
+    for ticket in &tickets {
+      let refund = self
+        .cancel_ticket(
+          cancel_ticket::Form {
+            trip_id: ticket.trip_id(),
+            seat_index: ticket.seat(),
+            stop_index: ticket.first_stop(),
+            requested_at: form.requested_at,
+          },
+          provider,
+        )
+        .map_err(Error::Ticket)?;
+      cancelled.push(refund);
+    }
+    for payment_id in authorized {
+      self
+        .void_payment(payment_id, provider)
+        .map_err(Error::Payment)?;
+    }
+
+    self
+      .seat_reservations
+      .retain(|sr| sr.trip_id() != form.trip_id);
+    self.seat_reservations.extend(kept);
+    for reservation in &released {
+      self.release_promo_code(reservation);
+    }
+    for order_id in orders {
+      release_from_order(self, order_id);
+      // Only created payments are left, which follow without the provider.
+      self
+        .follow_total(order_id, provider)
+        .map_err(Error::Payment)?;
+    }
+    self.seat_blocks.retain(|b| b.trip_id != form.trip_id);
+    self.seat_blocks.extend(blocks);
+    if let Some(trip) = self.trips.iter_mut().find(|t| t.id == form.trip_id) {
+      trip.vehicle = Vehicle::Bus(form.bus_id);
+    }
+    Ok(Swap {
+      cancelled,
+      released,
+      dropped_blocks,
+    })
+  }
+}
+
+/// Where everything on the trip ends up on the new bus.
+struct Plan {
+  kept: Vec<SeatReservation>,
+  tickets: Vec<SeatReservation>,
+  released: Vec<SeatReservation>,
+  blocks: Vec<SeatBlock>,
+  dropped_blocks: Vec<SeatBlock>,
+}
+
+/// Lays the trip's blocks and then its reservations out on `bus_model`.
+fn plan(database: &Database, form: &Form, bus_model: &BusModel) -> Result<Plan, Error> {
+  let current = database
+    .build_aggregate(form.trip_id)
+    .map_err(Error::Reservation)?;
+  let old_model = current.bus_model();
+  let new = database
+    .build_aggregate(form.trip_id)
+    .map_err(Error::Reservation)?
+    .with_bus_model(bus_model);
+
+  let mut occupied = BoolMatrix::new(new.stops_count(), new.seats_count());
+  let mut blocks: Vec<SeatBlock> = vec![];
+  let mut dropped_blocks: Vec<SeatBlock> = vec![];
+  for block in database
+    .seat_blocks
+    .iter()
+    .filter(|b| b.trip_id == form.trip_id)
+  {
+    match same_seat(old_model, bus_model, block.seat) {
+      Some(seat) if block.to < new.stops_count() => {
+        let moved = SeatBlock {
+          seat,
+          ..block.clone()
+        };
+        moved.apply(&mut occupied);
+        blocks.push(moved);
+      }
+      _ => dropped_blocks.push(block.clone()),
+    }
+  }
+
+  let mut kept: Vec<SeatReservation> = vec![];
+  let mut displaced: Vec<(SeatReservation, Eligibility)> = vec![];
+  for reservation in database
+    .seat_reservations
+    .iter()
+    .filter(|sr| sr.trip_id() == form.trip_id)
+  {
+    let eligibility = Eligibility {
+      class_id: old_model.seat(reservation.seat()).and_then(|s| s.class_id),
+      attributes: attributes(database, old_model, reservation),
+    };
+    match same_seat(old_model, bus_model, reservation.seat()).and_then(|seat| {
+      place(
+        &mut occupied,
+        &new,
+        reservation,
+        seat,
+        &eligibility,
+        form.requested_at,
+      )
+    }) {
+      Some(moved) => kept.push(moved),
+      None => displaced.push((reservation.clone(), eligibility)),
+    }
+  }
+  let mut bumped: Vec<SeatReservation> = vec![];
+  for (reservation, eligibility) in displaced {
+    match (0..new.seats_count()).find_map(|seat| {
+      place(
+        &mut occupied,
+        &new,
+        &reservation,
+        seat,
+        &eligibility,
+        form.requested_at,
+      )
+    }) {
+      Some(moved) => kept.push(moved),
+      None => bumped.push(reservation),
+    }
+  }
+  let (tickets, released): (Vec<SeatReservation>, Vec<SeatReservation>) = bumped
+    .into_iter()
+    .partition(|sr| sr.status() == seat_reservation::Status::Ticketed);
+  Ok(Plan {
+    kept,
+    tickets,
+    released,
+    blocks,
+    dropped_blocks,
+  })
+}
+
+/// The seat of `new` with the label `seat` has on `old`, or the same index
+/// when either bus has no layout.
+fn same_seat(old: &BusModel, new: &BusModel, seat: usize) -> Option<usize> {
+  match (old.seat(seat), &new.layout) {
+    (Some(old_seat), Some(_)) => new.seat_index(&old_seat.label),
+    _ => (seat < new.seats_count()).then_some(seat),
+  }
+}
+
+/// What a passenger may sit on: the class they bought and the restricted
+/// seats they qualify for.
+struct Eligibility {
+  class_id: Option<ClassId>,
+  attributes: Vec<PassengerAttribute>,
+}
+
+/// The passenger's own needs, or for an anonymous one whatever their current
+/// seat's restriction required, since they were let onto it.
+fn attributes(
+  database: &Database,
+  model: &BusModel,
+  reservation: &SeatReservation,
+) -> Vec<PassengerAttribute> {
+  reservation
+    .passenger_id()
+    .and_then(|id| database.show_passenger(id))
+    .map_or_else(
+      || {
+        model
+          .seat(reservation.seat())
+          .and_then(|s| s.restriction)
+          .map(|r| vec![r.requires])
+          .unwrap_or_default()
+      },
+      |passenger| passenger.attributes.clone(),
+    )
+}
+
+/// Brings an order's total in line with its remaining seats, cancelling it
+/// when none is left.
+fn release_from_order(database: &mut Database, order_id: order::Id) {
+  let total = database.order_total(order_id).ok().flatten();
+  let empty = !database
+    .seat_reservations
+    .iter()
+    .any(|sr| sr.order_id() == Some(order_id));
+  if let Some(order) = database.orders.iter_mut().find(|o| o.id == order_id) {
+    order.total = total;
+    if empty {
+      order.status = order::Status::Cancelled;
+    }
+  }
+}
+
+/// Rebuilds `reservation` on `seat` of the new bus if the passenger may sit
+/// there and it is free for their stops, marking it as taken in `occupied`.
+/// A seat matches any class when either bus does not tell classes apart.
+fn place(
+  occupied: &mut BoolMatrix,
+  new: &Aggregate,
+  reservation: &SeatReservation,
+  seat: usize,
+  eligibility: &Eligibility,
+  at: Timestamp,
+) -> Option<SeatReservation> {
+  if seat >= occupied.height || reservation.last_stop() >= occupied.width {
+    return None;
+  }
+  let class_id = new.bus_model().seat(seat).and_then(|s| s.class_id);
+  if eligibility.class_id.is_some() && class_id.is_some() && eligibility.class_id != class_id {
+    return None;
+  }
+  if new
+    .unmet_restriction(seat, reservation.first_stop(), &eligibility.attributes, at)
+    .is_some()
+  {
+    return None;
+  }
+  let moved = reservation.moved_to(seat, occupied.height);
+  *occupied = BoolMatrix::try_add(occupied, &moved.matrix)?;
+  Some(moved)
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      bus_model,
+      fixtures::{class_database, ordered, CAMA, SEMI_CAMA},
+      money::{Currency, Money},
+      order::Order,
+      passenger::{self, Passenger},
+      payment_provider::FakeProvider,
+      route::{self, Route},
+      seat_block::{self, Reason},
+      seat_layout::{Restriction, Seat, SeatLayout},
+    },
+  };
+
+  fn database() -> Database {
+    Database {
+      trips: vec![Trip {
+        id: trip::Id(0),
+        route_id: route::Id(0),
+        ..Default::default()
+      }],
+      routes: vec![Route {
+        id: route::Id(0),
+        ..Default::default()
+      }],
+      route_stops: (0..3)
+        .map(|index| route::Stop {
+          route_id: route::Id(0),
+          index,
+          ..Default::default()
+        })
+        .collect(),
+      buses: vec![
+        Bus {
+          id: bus::Id(0),
+          bus_model_id: bus_model::Id(0),
+        },
+        Bus {
+          id: bus::Id(1),
+          bus_model_id: bus_model::Id(1),
+        },
+      ],
+      bus_models: vec![
+        BusModel {
+          id: bus_model::Id(0),
+          number_of_seats: 4,
+          ..Default::default()
+        },
+        BusModel {
+          id: bus_model::Id(1),
+          number_of_seats: 2,
+          ..Default::default()
+        },
+      ],
+      seat_reservations: vec![
+        SeatReservation::new(trip::Id(0), 0, 0, 1, 4, 3),
+        SeatReservation::new(trip::Id(0), 3, 0, 1, 4, 3),
+        SeatReservation::new(trip::Id(0), 2, 1, 2, 4, 3),
+        SeatReservation::new(trip::Id(0), 1, 0, 2, 4, 3),
+        SeatReservation::new(trip::Id(1), 3, 0, 2, 4, 3),
+      ],
+      ..Default::default()
+    }
+  }
+
+  fn form(bus_id: u64) -> Form {
+    Form {
+      trip_id: trip::Id(0),
+      bus_id: bus::Id(bus_id),
+      turnaround: Duration::ZERO,
+      requested_at: Timestamp(0),
+    }
+  }
+
+  #[test]
+  fn bus_not_found() {
+    let mut database = database();
+
+    assert_eq!(
+      database.swap_bus(form(9), &mut FakeProvider::default()),
+      Err(Error::BusNotFound)
+    );
+  }
+
+  #[test]
+  fn reseats_and_reports_unaccommodated() {
+    let mut database = database();
+
+    let result = database.swap_bus(form(1), &mut FakeProvider::default());
+
+    assert_eq!(
+      result,
+      Ok(Swap {
+        released: vec![SeatReservation::new(trip::Id(0), 3, 0, 1, 4, 3)],
+        ..Swap::default()
+      })
+    );
+    assert_eq!(database.trips[0].vehicle, Vehicle::Bus(bus::Id(1)));
+    assert_eq!(
+      database.seat_reservations,
+      vec![
+        SeatReservation::new(trip::Id(1), 3, 0, 2, 4, 3),
+        SeatReservation::new(trip::Id(0), 0, 0, 1, 2, 3),
+        SeatReservation::new(trip::Id(0), 1, 0, 2, 2, 3),
+        SeatReservation::new(trip::Id(0), 0, 1, 2, 2, 3),
+      ]
+    );
+  }
+
+  /// The class test bus swapped for one with a cama seat, a wheelchair
+  /// semi-cama seat and a plain semi-cama seat blocked on the last leg.
+  #[test]
+  fn respects_classes_restrictions_and_blocks() {
+    let mut database = class_database();
+    let seat = |label: &str, class_id, restriction| Seat {
+      label: label.to_owned(),
+      class_id: Some(class_id),
+      restriction,
+      ..Default::default()
+    };
+    database.buses.push(Bus {
+      id: bus::Id(1),
+      bus_model_id: bus_model::Id(1),
+    });
+    database.bus_models.push(BusModel {
+      id: bus_model::Id(1),
+      number_of_seats: 3,
+      layout: Some(SeatLayout {
+        decks: 1,
+        seats: vec![
+          seat("1", CAMA, None),
+          seat(
+            "3",
+            SEMI_CAMA,
+            Some(Restriction {
+              requires: PassengerAttribute::Wheelchair,
+              release_before: None,
+            }),
+          ),
+          seat("2", SEMI_CAMA, None),
+        ],
+        ..Default::default()
+      }),
+      ..Default::default()
+    });
+    database.passengers.push(Passenger {
+      id: passenger::Id(0),
+      attributes: vec![PassengerAttribute::Wheelchair],
+      ..Default::default()
+    });
+    database.orders.push(Order::default());
+    let block = |id, seat, from| SeatBlock {
+      id: seat_block::Id(id),
+      trip_id: trip::Id(0),
+      seat,
+      from,
+      to: 2,
+      reason: Reason::OutOfService,
+    };
+    database.seat_blocks = vec![block(0, 2, 1), block(1, 4, 0)];
+    let cama = SeatReservation::new(trip::Id(0), 1, 0, 2, 5, 3);
+    let mut ticket = SeatReservation::new(trip::Id(0), 2, 0, 2, 5, 3);
+    ticket.set_order_id(Some(order::Id(0)));
+    ticket.set_status(seat_reservation::Status::Ticketed);
+    let mut wheelchair = SeatReservation::new(trip::Id(0), 3, 0, 2, 5, 3);
+    wheelchair.set_passenger_id(Some(passenger::Id(0)));
+    database.seat_reservations = vec![cama, ticket, wheelchair];
+
+    let swap = database.swap_bus(form(1), &mut FakeProvider::default());
+
+    assert_eq!(
+      swap.map(|s| (s.cancelled.len(), s.released, s.dropped_blocks)),
+      Ok((1, vec![], vec![block(1, 4, 0)]))
+    );
+    assert_eq!(
+      database
+        .seat_reservations
+        .iter()
+        .map(|sr| (sr.seat(), sr.passenger_id()))
+        .collect::<Vec<_>>(),
+      [(0, None), (1, Some(passenger::Id(0)))]
+    );
+    assert_eq!(database.seat_blocks, [block(0, 2, 1)]);
+    assert_eq!(database.orders[0].status, order::Status::Cancelled);
+  }
+
+  /// The order of `ordered` with its trip 0 seat on a bus without seats.
+  fn swap_ordered(authorize: bool) -> (Database, FakeProvider, Result<Swap, Error>) {
+    let mut database = ordered();
+    let mut provider = FakeProvider::default();
+    if authorize {
+      let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    }
+    database.buses.push(Bus {
+      id: bus::Id(1),
+      bus_model_id: bus_model::Id(1),
+    });
+    database.bus_models.push(BusModel {
+      id: bus_model::Id(1),
+      ..Default::default()
+    });
+    let swap = database.swap_bus(form(1), &mut provider);
+    (database, provider, swap)
+  }
+
+  #[test]
+  fn created_payment_follows_the_order_total() {
+    let (database, _, swap) = swap_ordered(false);
+
+    assert_eq!(swap.map(|s| s.released.len()), Ok(1));
+    assert_eq!(
+      database.orders[0].total,
+      Some(Money::new(1_000, Currency::XXX))
+    );
+    assert_eq!(
+      (database.payments[0].status, database.payments[0].amount),
+      (payment::Status::Created, Money::new(1_000, Currency::XXX))
+    );
+  }
+
+  #[test]
+  fn authorized_payment_is_voided() {
+    let (database, provider, swap) = swap_ordered(true);
+
+    assert_eq!(swap.map(|s| s.released.len()), Ok(1));
+    assert_eq!(database.payments[0].status, payment::Status::Voided);
+    assert_eq!(provider.calls.last().map(|call| call.0), Some("void"));
+  }
+
+  #[test]
+  fn orders_with_seats_apart_are_settled_once() {
+    let mut provider = FakeProvider::default();
+    let mut database = ordered();
+    let _ = database.authorize_payment(payment::Id(0), &mut provider);
+    database.bus_models[0].number_of_seats = 3;
+    database.orders.push(Order {
+      id: order::Id(1),
+      ..Default::default()
+    });
+    for (seat, order_id) in [(1, 1), (2, 0)] {
+      let mut reservation = SeatReservation::new(trip::Id(0), seat, 0, 1, 3, 2);
+      reservation.set_order_id(Some(order::Id(order_id)));
+      database.seat_reservations.push(reservation);
+    }
+    database.buses.push(Bus {
+      id: bus::Id(1),
+      bus_model_id: bus_model::Id(1),
+    });
+    database.bus_models.push(BusModel {
+      id: bus_model::Id(1),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      database
+        .swap_bus(form(1), &mut provider)
+        .map(|s| s.released.len()),
+      Ok(3)
+    );
+    assert_eq!(database.payments[0].status, payment::Status::Voided);
+    assert_eq!(
+      provider
+        .calls
+        .iter()
+        .filter(|call| call.0 == "void")
+        .count(),
+      1
+    );
+  }
+}
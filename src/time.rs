@@ -0,0 +1,473 @@
+//! Minimal calendar and instant types. Instants are UTC seconds since the Unix
+//! epoch; wall-clock values only exist together with a `UtcOffset`, or with a
+//! `TimeZone` that picks the offset in force at each instant.
+
+use std::time::Duration;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+  pub fn from_local(date: Date, time: Time, offset: UtcOffset) -> Self {
+    Self(date.days_since_epoch() * SECONDS_PER_DAY + time.seconds_of_day() - offset.seconds())
+  }
+
+  /// The wall time in `zone`. A wall time skipped when daylight saving
+  /// starts is read in standard time, so it lands after the change; one
+  /// repeated when it ends is taken the first time round.
+  pub fn from_zoned(date: Date, time: Time, zone: TimeZone) -> Self {
+    let standard = Self::from_local(date, time, zone.standard);
+    zone
+      .daylight
+      .map(|daylight| Self::from_local(date, time, daylight.offset))
+      .filter(|&candidate| zone.offset_at(candidate) != zone.standard)
+      .unwrap_or(standard)
+  }
+
+  pub fn to_local(self, offset: UtcOffset) -> (Date, Time) {
+    let local = self.0.saturating_add(offset.seconds());
+    let date = Date::from_days_since_epoch(local.div_euclid(SECONDS_PER_DAY));
+    let time = Time::from_seconds_of_day(local.rem_euclid(SECONDS_PER_DAY));
+    (date, time)
+  }
+
+  #[allow(dead_code)]
+  pub fn to_zoned(self, zone: TimeZone) -> (Date, Time) {
+    self.to_local(zone.offset_at(self))
+  }
+
+  /// Whole seconds later, saturating at the end of time.
+  pub fn add(self, duration: Duration) -> Self {
+    Self(
+      i64::try_from(duration.as_secs())
+        .ok()
+        .and_then(|seconds| self.0.checked_add(seconds))
+        .unwrap_or(i64::MAX),
+    )
+  }
+
+  /// Whole seconds earlier, saturating at the start of time.
+  pub fn sub(self, duration: Duration) -> Self {
+    Self(
+      i64::try_from(duration.as_secs())
+        .ok()
+        .and_then(|seconds| self.0.checked_sub(seconds))
+        .unwrap_or(i64::MIN),
+    )
+  }
+}
+
+/// Fixed offset from UTC in minutes, east positive, e.g. -180 for Chile in
+/// summer and -240 in winter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UtcOffset(pub i16);
+
+impl UtcOffset {
+  pub const UTC: Self = Self(0);
+
+  const fn seconds(self) -> i64 {
+    self.0 as i64 * 60
+  }
+}
+
+/// Local time rules of a place: its standard offset and, where it applies, a
+/// yearly daylight saving period.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeZone {
+  pub standard: UtcOffset,
+  pub daylight: Option<DaylightSaving>,
+}
+
+impl TimeZone {
+  #[allow(dead_code)]
+  pub const UTC: Self = Self::fixed(UtcOffset::UTC);
+
+  /// A place that keeps the same offset all year.
+  pub const fn fixed(offset: UtcOffset) -> Self {
+    Self {
+      standard: offset,
+      daylight: None,
+    }
+  }
+
+  pub fn offset_at(self, at: Timestamp) -> UtcOffset {
+    let Some(daylight) = self.daylight else {
+      return self.standard;
+    };
+    let year = at.to_local(self.standard).0.year;
+    let (Some(starts), Some(ends)) = (
+      daylight.starts.instant(year, self.standard),
+      daylight.ends.instant(year, daylight.offset),
+    ) else {
+      return self.standard;
+    };
+    // South of the equator the period spans the new year.
+    let in_daylight = if starts < ends {
+      starts <= at && at < ends
+    } else {
+      at >= starts || at < ends
+    };
+    if in_daylight {
+      daylight.offset
+    } else {
+      self.standard
+    }
+  }
+}
+
+/// E.g. Chile keeps -180 from early September to early April and -240 the
+/// rest of the year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DaylightSaving {
+  pub offset: UtcOffset,
+  /// In local standard time.
+  pub starts: Transition,
+  /// In local daylight time.
+  pub ends: Transition,
+}
+
+/// The `week`th `weekday` of `month` at `at`, where week 5 is the last one
+/// of the month.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+  pub month: u8,
+  pub week: u8,
+  pub weekday: Weekday,
+  pub at: Time,
+}
+
+impl Transition {
+  pub fn date(self, year: i32) -> Option<Date> {
+    if !(1..=5).contains(&self.week) {
+      return None;
+    }
+    let first = Date::new(year, self.month, 1)?;
+    let position = |weekday: Weekday| Weekday::ALL.iter().position(|&w| w == weekday);
+    let ahead = (position(self.weekday)? + 7 - position(first.weekday())?) % 7;
+    let mut day = 1 + ahead + 7 * usize::from(self.week - 1);
+    while day > usize::from(days_in_month(year, self.month)) {
+      day -= 7;
+    }
+    Date::new(year, self.month, u8::try_from(day).ok()?)
+  }
+
+  fn instant(self, year: i32, offset: UtcOffset) -> Option<Timestamp> {
+    Some(Timestamp::from_local(self.date(year)?, self.at, offset))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+  pub year: i32,
+  pub month: u8,
+  pub day: u8,
+}
+
+impl Date {
+  pub const fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+    if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+      return None;
+    }
+    Some(Self { year, month, day })
+  }
+
+  /// Days from 1970-01-01, following Howard Hinnant's `days_from_civil`.
+  pub fn days_since_epoch(self) -> i64 {
+    let year = i64::from(self.year) - i64::from(self.month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(self.month);
+    let day_of_year =
+      (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+  }
+
+  #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+  pub fn from_days_since_epoch(days: i64) -> Self {
+    let days = days.saturating_add(719_468);
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+      (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u8;
+    let month = if shifted_month < 10 {
+      shifted_month + 3
+    } else {
+      shifted_month - 9
+    } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let year = i32::try_from(year).unwrap_or(if year < 0 { i32::MIN } else { i32::MAX });
+    Self { year, month, day }
+  }
+
+  pub fn weekday(self) -> Weekday {
+    // 1970-01-01 was a Thursday.
+    Weekday::ALL[usize::try_from((self.days_since_epoch() + 3).rem_euclid(7)).unwrap_or(0)]
+  }
+
+  pub fn next(self) -> Self {
+    Self::from_days_since_epoch(self.days_since_epoch().saturating_add(1))
+  }
+}
+
+const fn days_in_month(year: i32, month: u8) -> u8 {
+  match month {
+    2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+  pub hour: u8,
+  pub minute: u8,
+}
+
+impl Time {
+  #[allow(dead_code)]
+  pub const fn new(hour: u8, minute: u8) -> Option<Self> {
+    if hour > 23 || minute > 59 {
+      return None;
+    }
+    Some(Self { hour, minute })
+  }
+
+  const fn seconds_of_day(self) -> i64 {
+    (self.hour as i64 * 60 + self.minute as i64) * 60
+  }
+
+  #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+  const fn from_seconds_of_day(seconds: i64) -> Self {
+    Self {
+      hour: (seconds / 3600) as u8,
+      minute: (seconds % 3600 / 60) as u8,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Weekday {
+  Monday,
+  Tuesday,
+  Wednesday,
+  Thursday,
+  Friday,
+  Saturday,
+  Sunday,
+}
+
+impl Weekday {
+  pub const ALL: [Self; 7] = [
+    Self::Monday,
+    Self::Tuesday,
+    Self::Wednesday,
+    Self::Thursday,
+    Self::Friday,
+    Self::Saturday,
+    Self::Sunday,
+  ];
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn date_round_trip() {
+    for days in [-719_468, -1, 0, 1, 59, 365, 11_016, 20_000, 2_932_896] {
+      assert_eq!(Date::from_days_since_epoch(days).days_since_epoch(), days);
+    }
+    assert_eq!(
+      Date::from_days_since_epoch(0),
+      Date {
+        year: 1970,
+        month: 1,
+        day: 1
+      }
+    );
+    assert_eq!(
+      Date::new(2024, 2, 29).map(Date::days_since_epoch),
+      Some(19_782)
+    );
+    assert_eq!(Date::new(2023, 2, 29), None);
+  }
+
+  #[test]
+  fn weekday() {
+    assert_eq!(
+      Date::new(1970, 1, 1).map(Date::weekday),
+      Some(Weekday::Thursday)
+    );
+    assert_eq!(
+      Date::new(2026, 10, 19).map(Date::weekday),
+      Some(Weekday::Monday)
+    );
+  }
+
+  #[test]
+  fn local_round_trip() {
+    let date = Date {
+      year: 2026,
+      month: 1,
+      day: 1,
+    };
+    let time = Time {
+      hour: 1,
+      minute: 30,
+    };
+    let santiago = UtcOffset(-180);
+    let timestamp = Timestamp::from_local(date, time, santiago);
+
+    assert_eq!(timestamp.to_local(santiago), (date, time));
+    assert_eq!(
+      timestamp.to_local(UtcOffset::UTC),
+      (
+        date,
+        Time {
+          hour: 4,
+          minute: 30
+        }
+      )
+    );
+  }
+
+  const CHILE: TimeZone = TimeZone {
+    standard: UtcOffset(-240),
+    daylight: Some(DaylightSaving {
+      offset: UtcOffset(-180),
+      starts: Transition {
+        month: 9,
+        week: 1,
+        weekday: Weekday::Saturday,
+        at: Time {
+          hour: 23,
+          minute: 59,
+        },
+      },
+      ends: Transition {
+        month: 4,
+        week: 1,
+        weekday: Weekday::Saturday,
+        at: Time {
+          hour: 23,
+          minute: 59,
+        },
+      },
+    }),
+  };
+
+  fn date(year: i32, month: u8, day: u8) -> Date {
+    Date::new(year, month, day).unwrap_or_default()
+  }
+
+  #[test]
+  fn transition_dates() {
+    let first_saturday = |month: u8, week: u8| {
+      Transition {
+        month,
+        week,
+        weekday: Weekday::Saturday,
+        at: Time::default(),
+      }
+      .date(2026)
+    };
+
+    assert_eq!(first_saturday(9, 1), Some(date(2026, 9, 5)));
+    assert_eq!(first_saturday(4, 1), Some(date(2026, 4, 4)));
+    assert_eq!(first_saturday(10, 5), Some(date(2026, 10, 31)));
+    assert_eq!(first_saturday(2, 5), Some(date(2026, 2, 28)));
+    assert_eq!(first_saturday(2, 0), None);
+  }
+
+  #[test]
+  fn zoned_times_follow_daylight_saving() {
+    let eight = Time { hour: 8, minute: 0 };
+    let summer = Timestamp::from_zoned(date(2026, 1, 15), eight, CHILE);
+    let winter = Timestamp::from_zoned(date(2026, 7, 15), eight, CHILE);
+
+    assert_eq!(
+      summer,
+      Timestamp::from_local(date(2026, 1, 15), eight, UtcOffset(-180))
+    );
+    assert_eq!(
+      winter,
+      Timestamp::from_local(date(2026, 7, 15), eight, UtcOffset(-240))
+    );
+    assert_eq!(summer.to_zoned(CHILE), (date(2026, 1, 15), eight));
+    assert_eq!(winter.to_zoned(CHILE), (date(2026, 7, 15), eight));
+    assert_eq!(
+      TimeZone::fixed(UtcOffset(-180)).offset_at(winter),
+      UtcOffset(-180)
+    );
+  }
+
+  #[test]
+  fn zoned_times_around_changes() {
+    // Clocks go from 23:59 to 00:59 on 2026-09-05, so 00:30 is skipped.
+    let skipped = Timestamp::from_zoned(
+      date(2026, 9, 6),
+      Time {
+        hour: 0,
+        minute: 30,
+      },
+      CHILE,
+    );
+    assert_eq!(
+      skipped.to_zoned(CHILE),
+      (
+        date(2026, 9, 6),
+        Time {
+          hour: 1,
+          minute: 30
+        }
+      )
+    );
+
+    // Clocks go from 23:59 back to 22:59 on 2026-04-04, so 23:30 happens twice.
+    let repeated = Timestamp::from_zoned(
+      date(2026, 4, 4),
+      Time {
+        hour: 23,
+        minute: 30,
+      },
+      CHILE,
+    );
+    assert_eq!(
+      repeated,
+      Timestamp::from_local(
+        date(2026, 4, 4),
+        Time {
+          hour: 23,
+          minute: 30
+        },
+        UtcOffset(-180)
+      )
+    );
+  }
+
+  #[test]
+  fn arithmetic_saturates() {
+    let far = Duration::from_secs(u64::MAX);
+
+    assert_eq!(Timestamp(10).add(Duration::from_secs(5)), Timestamp(15));
+    assert_eq!(Timestamp(10).sub(Duration::from_secs(15)), Timestamp(-5));
+    assert_eq!(Timestamp(10).add(far), Timestamp(i64::MAX));
+    assert_eq!(Timestamp(10).sub(far), Timestamp(i64::MIN));
+    assert_eq!(
+      Timestamp(i64::MAX - 1).add(Duration::from_secs(2)),
+      Timestamp(i64::MAX)
+    );
+    assert_eq!(Timestamp(i64::MAX).to_local(UtcOffset(60)).0.year, i32::MAX);
+    assert_eq!(
+      Timestamp(i64::MIN).to_local(UtcOffset(-60)).0.year,
+      i32::MIN
+    );
+  }
+}
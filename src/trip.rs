@@ -1,11 +1,45 @@
-use crate::{bus, route};
+use crate::{
+  bus, bus_model, route, schedule, stop,
+  time::{TimeZone, Timestamp},
+};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Id(pub u64);
 
+/// What runs a trip: a given bus, or a bus model until one is assigned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Vehicle {
+  Bus(bus::Id),
+  BusModel(bus_model::Id),
+}
+
+impl Default for Vehicle {
+  fn default() -> Self {
+    Self::Bus(bus::Id::default())
+  }
+}
+
+impl Vehicle {
+  pub const fn bus_id(self) -> Option<bus::Id> {
+    match self {
+      Self::Bus(id) => Some(id),
+      Self::BusModel(_) => None,
+    }
+  }
+}
+
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct Trip {
   pub id: Id,
   pub route_id: route::Id,
-  pub bus_id: bus::Id,
+  pub vehicle: Vehicle,
+  pub departure: Timestamp,
+  /// Zone the timetable is presented in; `departure` itself is UTC.
+  pub time_zone: TimeZone,
+  /// Set on trips materialised from a recurring schedule.
+  pub schedule_id: Option<schedule::Id>,
+  /// Platform of the origin station the trip leaves from, overriding the
+  /// route's first stop.
+  pub departure_platform: Option<stop::Id>,
 }
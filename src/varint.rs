@@ -0,0 +1,40 @@
+//! LEB128 encoding for unsigned integers, used by the compact binary formats.
+
+pub fn write(buffer: &mut Vec<u8>, mut value: u64) {
+  loop {
+    #[allow(clippy::cast_possible_truncation)]
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      buffer.push(byte);
+      return;
+    }
+    buffer.push(byte | 0x80);
+  }
+}
+
+/// Reads a value starting at `*cursor`, advancing the cursor past it.
+pub fn read(bytes: &[u8], cursor: &mut usize) -> Option<u64> {
+  let mut value: u64 = 0;
+  let mut shift: u32 = 0;
+  loop {
+    let byte = *bytes.get(*cursor)?;
+    *cursor += 1;
+    if shift >= 64 {
+      return None;
+    }
+    value |= u64::from(byte & 0x7f).checked_shl(shift)?;
+    if byte & 0x80 == 0 {
+      return Some(value);
+    }
+    shift += 7;
+  }
+}
+
+pub fn write_usize(buffer: &mut Vec<u8>, value: usize) {
+  write(buffer, value as u64);
+}
+
+pub fn read_usize(bytes: &[u8], cursor: &mut usize) -> Option<usize> {
+  usize::try_from(read(bytes, cursor)?).ok()
+}